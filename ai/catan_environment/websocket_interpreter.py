@@ -43,7 +43,7 @@ class WebSocketInterpreter:
         observation.append(game_json["bank"]["resource_cards"]["Sheep"])
 
         # Development Cards in bank.
-        observation.append(len(game_json["bank"]["development_cards"]))
+        observation.append(game_json["bank"]["num_development_cards"])
 
         # Player Resources
         current_player = game_json["players"][game_json["current_player_id"]]
@@ -1,7 +1,49 @@
+use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 
+use crate::game::action_result::ActionError;
+use crate::game::game::Game;
+use crate::game::resource::ResourceCard;
+
+// An action a player can take on their turn.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub enum Action {
+    RollDice,
+    // Moves the robber after a seven is rolled, and steals a card from the victim.
+    // There is no victim when the robber is moved to a tile without any of the other players' buildings.
+    MoveRobber { tile: usize, victim: Option<usize> },
+    // Builds a settlement, or places a starting settlement during setup.
+    BuildSettlement { node: usize },
+    // Builds a road, or places a starting road during setup or a free road from road building.
+    BuildRoad { edge: usize },
+    BuildCity { node: usize },
+    // Trades with the bank, giving batches of resources at the player's rate for each, see Game::maritimeRates,
+    // and receiving one card for each batch.
+    MaritimeTrade { giving: HashMap<ResourceCard, usize>, receiving: HashMap<ResourceCard, usize> },
+    // Offers the trade to every other player, see AcceptTrade. Trades with the bank are made with MaritimeTrade.
+    OfferTrade { giving: HashMap<ResourceCard, usize>, receiving: HashMap<ResourceCard, usize> },
+    // The other players accept the offer with the given id out of turn, and player is ignored. The player who made
+    // the offer then confirms the trade with one player who accepted or countered, and the cards are exchanged.
+    AcceptTrade { offer_id: usize, player: usize },
+    // The other players decline the offer, and the player who made the offer withdraws it.
+    DeclineTrade { offer_id: usize },
+    // Responds to the offer with other terms, giving and receiving from the responding player's side.
+    CounterTrade { offer_id: usize, giving: HashMap<ResourceCard, usize>, receiving: HashMap<ResourceCard, usize> },
+    BuyDevelopmentCard,
+    PlayKnight { tile: usize, victim: Option<usize> },
+    PlayMonopoly { resource: ResourceCard },
+    // Plays road building, after which the player places two roads for free with BuildRoad.
+    PlayRoadBuilding,
+    PlayYearOfPlenty { first_resource: ResourceCard, second_resource: ResourceCard },
+    Discard { resources: HashMap<ResourceCard, usize> },
+    EndTurn
+}
+
+// The flat action format used by older clients, where the meaning of the metadata depends on the action type.
+// Moving the robber and playing a knight give the tile and then the player to steal from. Giving the acting player's
+// own id steals from nobody, for moving the robber to a tile without any other player's buildings.
 #[derive(Serialize, Deserialize, Clone)]
-pub struct Action {
+pub struct FlatAction {
     pub action_type: ActionType,
     pub action_metadata: [usize; 10]
 }
@@ -22,21 +64,97 @@ pub enum ActionType {
     EndTurn
 }
 
-// impl ActionType {
-//     fn value(&self) -> i32{
-//         match *self {
-//             ActionType::RollDice => 0,
-//             ActionType::PlaceRobber => 1,
-//             ActionType::PlaySettlement => 2,
-//             ActionType::PlayRoad => 3,
-//             ActionType::PlayCity => 4,
-//             ActionType::OfferTrade => 5,
-//             ActionType::AcceptTrade => 6,
-//             ActionType::DeclineTrade => 7,
-//             ActionType::PlayDevelopmentCard => 8,
-//             ActionType::DrawDevelopmentCard => 9,
-//             ActionType::Discard => 10,
-//             ActionType::EndTurn => 11
-//         }
-//     }
-// }
\ No newline at end of file
+// An action sent by a client, in either format.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ActionMessage {
+    Typed(Action),
+    Flat(FlatAction),
+}
+
+// A flat action is usually one action, but playing road building also places its two roads.
+impl TryFrom<FlatAction> for Vec<Action> {
+    type Error = ActionError;
+
+    fn try_from(flat_action: FlatAction) -> Result<Self, Self::Error> {
+        let metadata = flat_action.action_metadata;
+        let action = match flat_action.action_type {
+            ActionType::RollDice => Action::RollDice,
+            ActionType::PlaceRobber => Action::MoveRobber { tile: metadata[0], victim: Some(metadata[1]) },
+            ActionType::PlaySettlement => Action::BuildSettlement { node: metadata[0] },
+            ActionType::PlayRoad => Action::BuildRoad { edge: metadata[0] },
+            ActionType::PlayCity => Action::BuildCity { node: metadata[0] },
+            ActionType::OfferTrade => Action::OfferTrade {
+                giving: resources_from(&metadata[0..5]),
+                receiving: resources_from(&metadata[5..10])
+            },
+            // The flat format has no trade offer ids, so the offer is filled in by ActionMessage::toActions.
+            ActionType::AcceptTrade => Action::AcceptTrade { offer_id: 0, player: metadata[0] },
+            ActionType::DeclineTrade => Action::DeclineTrade { offer_id: 0 },
+            // The development card is given by 0 => Knight, 1 => Monopoly, 2 => Road Building, 4 => Year of Plenty.
+            // Road building is followed by the edges of the two roads it places.
+            ActionType::PlayDevelopmentCard => match metadata[0] {
+                0 => Action::PlayKnight { tile: metadata[1], victim: Some(metadata[2]) },
+                1 => Action::PlayMonopoly { resource: resource_from(metadata[1])? },
+                2 => return Ok(vec![
+                    Action::PlayRoadBuilding,
+                    Action::BuildRoad { edge: metadata[1] },
+                    Action::BuildRoad { edge: metadata[2] },
+                ]),
+                4 => Action::PlayYearOfPlenty {
+                    first_resource: resource_from(metadata[1])?,
+                    second_resource: resource_from(metadata[2])?
+                },
+                _ => return Err(ActionError::InvalidDevelopmentCard)
+            },
+            ActionType::DrawDevelopmentCard => Action::BuyDevelopmentCard,
+            ActionType::Discard => Action::Discard { resources: resources_from(&metadata[0..5]) },
+            ActionType::EndTurn => Action::EndTurn
+        };
+        Ok(vec![action])
+    }
+}
+
+#[allow(non_snake_case)]
+impl ActionMessage {
+    // Converts the message into the actions the player is taking in the game, see Game::takeActions. Older clients make
+    // bank trades with OfferTrade in the flat format, so a flat offer the player could make with the bank is a maritime trade.
+    // They also can't say which trade offer they respond to, which is only clear while one offer is open,
+    // and they steal from nobody by naming themselves as the victim.
+    pub fn toActions(self, game: &Game, player_id: usize) -> Result<Vec<Action>, ActionError> {
+        let actions = match self {
+            ActionMessage::Typed(action) => return Ok(vec![action]),
+            ActionMessage::Flat(flat_action) => Vec::<Action>::try_from(flat_action)?
+        };
+        let open_offer_id = || match game.openTradeOfferIds()[..] {
+            [offer_id] => Ok(offer_id),
+            [] => Err(ActionError::NoTradeOffer),
+            _ => Err(ActionError::AmbiguousTradeOffer)
+        };
+        actions.into_iter().map(|action| match action {
+            Action::OfferTrade { giving, receiving } => {
+                let maritime_trade = Action::MaritimeTrade { giving: giving.clone(), receiving: receiving.clone() };
+                if game.checkAction(&maritime_trade, player_id).is_ok() {
+                    return Ok(maritime_trade);
+                }
+                Ok(Action::OfferTrade { giving, receiving })
+            },
+            Action::AcceptTrade { player, .. } => Ok(Action::AcceptTrade { offer_id: open_offer_id()?, player }),
+            Action::DeclineTrade { .. } => Ok(Action::DeclineTrade { offer_id: open_offer_id()? }),
+            Action::MoveRobber { tile, victim } => Ok(Action::MoveRobber { tile, victim: victim.filter(|victim| *victim != player_id) }),
+            Action::PlayKnight { tile, victim } => Ok(Action::PlayKnight { tile, victim: victim.filter(|victim| *victim != player_id) }),
+            action => Ok(action)
+        }).collect()
+    }
+}
+
+// Resources in the flat format are indexed in the same order as ResourceCard::ALL.
+fn resource_from(index: usize) -> Result<ResourceCard, ActionError> {
+    ResourceCard::ALL.get(index).copied().ok_or(ActionError::InvalidDevelopmentCard)
+}
+
+fn resources_from(amounts: &[usize]) -> HashMap<ResourceCard, usize> {
+    ResourceCard::ALL.iter().copied().zip(amounts.iter().copied())
+        .filter(|(_, amount)| *amount > 0)
+        .collect()
+}
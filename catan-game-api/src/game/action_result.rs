@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+
+use crate::game::player::Player;
+use crate::game::resource::ResourceCard;
+
+// The outcome of taking an action: what changed if it was applied, or why it was rejected.
+pub type ActionResult = Result<StateDelta, ActionError>;
+
+// The reasons an action can be rejected.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum ActionError {
+    // The game has already been won.
+    GameOver,
+    // The action was taken by a player whose turn it isn't.
+    NotYourTurn,
+    // The action can't be taken at this point of the game or turn.
+    WrongPhase,
+    // The dice must be rolled before this action.
+    DiceNotRolled,
+    // The dice have already been rolled this turn.
+    DiceAlreadyRolled,
+    // The player doesn't have the cards the action costs or gives away.
+    InsufficientResources,
+    // The bank doesn't have the cards the action needs.
+    InsufficientBankResources,
+    // The node, edge or tile doesn't exist or can't be used for the action.
+    InvalidPosition,
+    // There is already a building on the node or edge.
+    PositionOccupied,
+    // A settlement can't be placed next to another settlement or city.
+    DistanceRule,
+    // The building isn't connected to one of the player's roads or buildings.
+    NotConnected,
+    // The player has no more of the building left to place.
+    NoPiecesLeft,
+    // There are no development cards left in the bank.
+    NoDevelopmentCardsLeft,
+    // The player doesn't have the development card.
+    DevelopmentCardUnavailable,
+    // The development card or its parameters are invalid.
+    InvalidDevelopmentCard,
+    // A development card has already been played this turn.
+    DevelopmentCardAlreadyPlayed,
+    // The trade offer is not valid.
+    InvalidTrade,
+    // There is no trade offer to respond to.
+    NoTradeOffer,
+    // Several trade offers are open, and the response in the flat format can't say which one it is to.
+    AmbiguousTradeOffer,
+    // A trade offer must be accepted or declined first.
+    TradeOfferPending,
+    // The robber can't be moved to the tile, or the chosen player can't be robbed.
+    InvalidRobberPlacement,
+    // The cards to discard are not valid.
+    InvalidDiscard,
+}
+
+// What changed in the game as a result of an action.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct StateDelta {
+    pub dice_roll: Option<usize>,
+    pub current_player_id: usize,
+    // Only players that were affected by the action are included.
+    pub players: Vec<PlayerDelta>,
+}
+
+// What changed for a single player as a result of an action.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PlayerDelta {
+    pub player_id: usize,
+    // Which cards changed is only shown to whoever controls the player, everyone else sees the total change.
+    pub resource_changes: HashMap<ResourceCard, i32>,
+    pub num_resource_cards_change: i32,
+    pub development_card_change: i32,
+    // Includes victory point cards, which only whoever controls the player sees. Everyone else sees the public change.
+    pub victory_point_change: i32,
+    #[serde(skip)]
+    pub public_victory_point_change: i32,
+    pub settlements_placed: Vec<usize>,
+    pub cities_placed: Vec<usize>,
+    pub roads_placed: Vec<usize>,
+}
+
+#[allow(non_snake_case)]
+impl StateDelta {
+    // Works out what changed between the players before and after an action.
+    pub fn between(players_before: &[Player], players_after: &[Player], dice_roll: Option<usize>, current_player_id: usize) -> Self {
+        let players = players_before.iter().zip(players_after.iter())
+            .map(|(before, after)| PlayerDelta::between(before, after))
+            .filter(|player_delta| !player_delta.isEmpty())
+            .collect();
+
+        StateDelta {
+            dice_roll,
+            current_player_id,
+            players
+        }
+    }
+
+    // Returns the changes as seen by whoever controls the given seat, see Game::view.
+    pub fn view(&self, seat: Option<usize>) -> Self {
+        let mut state_delta = self.clone();
+        for player_delta in state_delta.players.iter_mut() {
+            if seat.is_some_and(|seat| seat != player_delta.player_id) {
+                player_delta.resource_changes.clear();
+                player_delta.victory_point_change = player_delta.public_victory_point_change;
+            }
+        }
+        state_delta
+    }
+}
+
+#[allow(non_snake_case)]
+impl PlayerDelta {
+    pub fn between(before: &Player, after: &Player) -> Self {
+        let mut resource_changes = HashMap::new();
+        for resource in ResourceCard::ALL {
+            let change = *after.resource_cards.get(&resource).unwrap() as i32 - *before.resource_cards.get(&resource).unwrap() as i32;
+            if change != 0 {
+                resource_changes.insert(resource, change);
+            }
+        }
+
+        PlayerDelta {
+            player_id: after.id,
+            resource_changes,
+            num_resource_cards_change: after.numResourceCards() as i32 - before.numResourceCards() as i32,
+            development_card_change: after.numDevelopmentCards() as i32 - before.numDevelopmentCards() as i32,
+            victory_point_change: after.totalVictoryPoints() as i32 - before.totalVictoryPoints() as i32,
+            public_victory_point_change: after.victory_points as i32 - before.victory_points as i32,
+            settlements_placed: new_placements(&before.settlement_placements, &after.settlement_placements),
+            cities_placed: new_placements(&before.city_placements, &after.city_placements),
+            roads_placed: new_placements(&before.road_placements, &after.road_placements),
+        }
+    }
+
+    pub fn isEmpty(&self) -> bool {
+        self.resource_changes.is_empty() &&
+            self.num_resource_cards_change == 0 &&
+            self.development_card_change == 0 &&
+            self.victory_point_change == 0 &&
+            self.settlements_placed.is_empty() &&
+            self.cities_placed.is_empty() &&
+            self.roads_placed.is_empty()
+    }
+}
+
+// Returns the placements in after that aren't in before.
+fn new_placements(before: &[usize], after: &[usize]) -> Vec<usize> {
+    after.iter().filter(|placement| !before.contains(placement)).copied().collect()
+}
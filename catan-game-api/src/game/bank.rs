@@ -3,45 +3,43 @@ use rand::prelude::*;
 
 use crate::game::resource::ResourceCard;
 use crate::game::development::DevelopmentCard;
+use crate::game::edition::Edition;
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Bank {
     resource_cards: HashMap<ResourceCard, usize>,
-    development_cards: [DevelopmentCard; 25],
+    development_cards: Vec<DevelopmentCard>,
     development_card_pointer: usize
 }
 
 #[allow(non_snake_case)]
 impl Bank {
     // Initializes the resource cards and the development cards.
-    pub fn new() -> Bank {
+    // The 5-6 player extension has more of every card.
+    pub fn new(edition: Edition, rng: &mut impl Rng) -> Bank {
         let development_card_pointer = 0;
 
         // Initialize resource cards
-        let mut resource_cards = HashMap::new();
-        resource_cards.insert(ResourceCard::Ore, 19);
-        resource_cards.insert(ResourceCard::Wheat, 19);
-        resource_cards.insert(ResourceCard::Sheep, 19);
-        resource_cards.insert(ResourceCard::Brick, 19);
-        resource_cards.insert(ResourceCard::Lumber, 19);
+        let num_each_resource = match edition {
+            Edition::Base => 19,
+            Edition::FiveSixPlayer => 24,
+        };
+        let resource_cards = ResourceCard::ALL.iter().map(|resource| (*resource, num_each_resource)).collect();
 
-        // Initialize development cards.
-        let mut development_cards: [DevelopmentCard; 25] = core::array::from_fn(|index| {
-            if index < 13{
-                DevelopmentCard::Knight
-            } else if index < 16 {
-                DevelopmentCard::RoadBuilding
-            } else if index < 18 {
-                DevelopmentCard::YearOfPlenty
-            } else if index < 20 {
-                DevelopmentCard::Monopoly
-            } else  {
-                DevelopmentCard::VictoryPoint
-            }
-        });
-        let mut rng = rand::thread_rng();
-        development_cards.shuffle(&mut rng);
+        // Initialize development cards, as the number of knight, road building, year of plenty, monopoly and victory point cards.
+        let counts = match edition {
+            Edition::Base => [13, 3, 2, 2, 5],
+            Edition::FiveSixPlayer => [20, 3, 3, 3, 5],
+        };
+        let mut development_cards: Vec<DevelopmentCard> = [
+            DevelopmentCard::Knight,
+            DevelopmentCard::RoadBuilding,
+            DevelopmentCard::YearOfPlenty,
+            DevelopmentCard::Monopoly,
+            DevelopmentCard::VictoryPoint,
+        ].into_iter().zip(counts).flat_map(|(development_card, count)| std::iter::repeat_n(development_card, count)).collect();
+        development_cards.shuffle(rng);
 
         // println!("Successfully created new bank.");
         Bank {
@@ -53,7 +51,7 @@ impl Bank {
 
     // Returns the top of the development card stack.
     pub fn drawDevelopmentCard(&mut self) -> Option<&DevelopmentCard> {
-        if self.development_card_pointer == 25 {
+        if self.development_card_pointer == self.development_cards.len() {
             return None
         }
         let development_card = &self.development_cards[self.development_card_pointer];
@@ -61,28 +59,38 @@ impl Bank {
         Some(development_card)
     }
 
+    // Draws the given card instead of the top of the stack, as recorded in a game being played back.
+    // Returns None if none of the cards left are the given card.
+    pub fn drawNamedDevelopmentCard(&mut self, development_card: DevelopmentCard) -> Option<DevelopmentCard> {
+        let position = self.development_cards[self.development_card_pointer..].iter().position(|card| *card == development_card)?;
+        self.development_cards.swap(self.development_card_pointer, self.development_card_pointer + position);
+        return self.drawDevelopmentCard().copied();
+    }
+
+    // Shuffles the development cards that haven't been drawn yet, so a draw that was taken back doesn't show what comes next.
+    pub fn shuffleUndrawnDevelopmentCards(&mut self, rng: &mut impl Rng) {
+        self.development_cards[self.development_card_pointer..].shuffle(rng);
+    }
+
+    pub fn numDevelopmentCardsLeft(&self) -> usize {
+        return self.development_cards.len() - self.development_card_pointer;
+    }
+
     // Returns true if the number of resource cards is able to be drawn, else false.
     // If the resource cards can be drawn, they are removed from the deck.
     pub fn drawNumberOfResourceCards(&mut self, resource_card: ResourceCard, amount: usize) -> bool {
         match self.resource_cards.get(&resource_card) {
-            Some(num_in_pile) => {
-                if *num_in_pile >= amount {
-                    self.resource_cards.insert(resource_card, num_in_pile-amount);
-                    true
-                } else {
-                    false
-                }
+            Some(num_in_pile) if *num_in_pile >= amount => {
+                self.resource_cards.insert(resource_card, num_in_pile-amount);
+                true
             },
-            None => false
+            _ => false
         }
     }
 
     // Replaces the resource cards in the bank.
     pub fn replaceResourceCard(&mut self, resource_card: ResourceCard, amount: usize) {
-        match self.resource_cards.get(&resource_card) {
-            Some(num_in_pile) => {self.resource_cards.insert(resource_card, num_in_pile + amount);},
-            None => {}
-        }
+        if let Some(num_in_pile) = self.resource_cards.get(&resource_card) {self.resource_cards.insert(resource_card, num_in_pile + amount);}
     }
 
     pub fn amountOfResource(&self, resource: ResourceCard) -> usize {
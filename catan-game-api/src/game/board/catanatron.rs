@@ -0,0 +1,269 @@
+use serde::{Serialize, Deserialize};
+
+use crate::game::board::Board;
+use crate::game::board::hex::{board_edges, board_tiles, EdgeCoord, EdgeSide, HexCoord, NodeCoord, NodeCorner};
+use crate::game::board::topology::topology;
+use crate::game::edge::Edge;
+use crate::game::edition::Edition;
+use crate::game::node::Node;
+use crate::game::port::Port;
+use crate::game::resource::ResourceCard;
+use crate::game::terrain::Terrain;
+use crate::game::tile::Tile;
+
+// The map of a Catanatron game, as the "tiles" list of its game JSON. Each tile is given by its cube coordinates
+// [x, y, z], where x is this board's q and z is its r, so the center tile is [0, 0, 0] and [1, -1, 0] is east of it.
+// A port is a tile in the sea whose direction is the side facing the land, and a resource of null is a 3:1 port.
+// For example [{"coordinate":[0,0,0],"tile":{"id":0,"type":"RESOURCE_TILE","resource":"WOOD","number":6}},
+// {"coordinate":[3,-3,0],"tile":{"id":19,"type":"PORT","direction":"WEST","resource":null}},
+// {"coordinate":[1,2,-3],"tile":{"type":"WATER"}}, ...]. Only the base board can be read or written.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CatanatronTileEntry {
+    pub coordinate: [i32; 3],
+    pub tile: CatanatronTile,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronTile {
+    ResourceTile { id: usize, resource: CatanatronResource, number: i32 },
+    Desert { id: usize },
+    Port { id: usize, direction: CatanatronEdgeRef, resource: Option<CatanatronResource> },
+    Water,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronResource {
+    Wood,
+    Brick,
+    Sheep,
+    Wheat,
+    Ore,
+}
+
+// The corners of a tile, clockwise from the top, in the same order as HexCoord::nodes.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronNodeRef {
+    North,
+    Northeast,
+    Southeast,
+    South,
+    Southwest,
+    Northwest,
+}
+
+// The sides of a tile, clockwise from the north east, in the same order as HexCoord::edges.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronEdgeRef {
+    Northeast,
+    East,
+    Southeast,
+    Southwest,
+    West,
+    Northwest,
+}
+
+const NODE_REFS: [CatanatronNodeRef; 6] = [
+    CatanatronNodeRef::North,
+    CatanatronNodeRef::Northeast,
+    CatanatronNodeRef::Southeast,
+    CatanatronNodeRef::South,
+    CatanatronNodeRef::Southwest,
+    CatanatronNodeRef::Northwest,
+];
+
+const EDGE_REFS: [CatanatronEdgeRef; 6] = [
+    CatanatronEdgeRef::Northeast,
+    CatanatronEdgeRef::East,
+    CatanatronEdgeRef::Southeast,
+    CatanatronEdgeRef::Southwest,
+    CatanatronEdgeRef::West,
+    CatanatronEdgeRef::Northwest,
+];
+
+#[allow(non_snake_case)]
+impl CatanatronResource {
+    pub fn fromResourceCard(resource: ResourceCard) -> Self {
+        match resource {
+            ResourceCard::Lumber => CatanatronResource::Wood,
+            ResourceCard::Brick => CatanatronResource::Brick,
+            ResourceCard::Sheep => CatanatronResource::Sheep,
+            ResourceCard::Wheat => CatanatronResource::Wheat,
+            ResourceCard::Ore => CatanatronResource::Ore,
+        }
+    }
+
+    pub fn toResourceCard(&self) -> ResourceCard {
+        match self {
+            CatanatronResource::Wood => ResourceCard::Lumber,
+            CatanatronResource::Brick => ResourceCard::Brick,
+            CatanatronResource::Sheep => ResourceCard::Sheep,
+            CatanatronResource::Wheat => ResourceCard::Wheat,
+            CatanatronResource::Ore => ResourceCard::Ore,
+        }
+    }
+}
+
+// Converts between a tile's axial coordinates and Catanatron's cube coordinates.
+pub fn to_cube(hex: HexCoord) -> [i32; 3] {
+    [hex.q, hex.s(), hex.r]
+}
+
+pub fn from_cube(coordinate: [i32; 3]) -> Result<HexCoord, String> {
+    let [x, y, z] = coordinate;
+    if x + y + z != 0 {
+        return Err(format!("{:?} isn't a cube coordinate, since it doesn't add up to 0.", coordinate));
+    }
+    Ok(HexCoord::new(x, z))
+}
+
+// The corner of a tile, named as Catanatron does, and back. Every node is the north or south corner of some tile.
+pub fn node_from_ref(hex: HexCoord, direction: CatanatronNodeRef) -> NodeCoord {
+    hex.nodes()[NODE_REFS.iter().position(|node_ref| *node_ref == direction).unwrap()]
+}
+
+pub fn node_to_ref(node: NodeCoord) -> (HexCoord, CatanatronNodeRef) {
+    match node.corner {
+        NodeCorner::North => (node.hex, CatanatronNodeRef::North),
+        NodeCorner::South => (node.hex, CatanatronNodeRef::South),
+    }
+}
+
+pub fn edge_from_ref(hex: HexCoord, direction: CatanatronEdgeRef) -> EdgeCoord {
+    hex.edges()[EDGE_REFS.iter().position(|edge_ref| *edge_ref == direction).unwrap()]
+}
+
+pub fn edge_to_ref(edge: EdgeCoord) -> (HexCoord, CatanatronEdgeRef) {
+    match edge.side {
+        EdgeSide::NorthEast => (edge.hex, CatanatronEdgeRef::Northeast),
+        EdgeSide::West => (edge.hex, CatanatronEdgeRef::West),
+        EdgeSide::NorthWest => (edge.hex, CatanatronEdgeRef::Northwest),
+    }
+}
+
+fn port_from_resource(resource: Option<CatanatronResource>) -> Port {
+    match resource.map(|resource| resource.toResourceCard()) {
+        None => Port::ThreeToOne,
+        Some(ResourceCard::Lumber) => Port::Lumber,
+        Some(ResourceCard::Brick) => Port::Brick,
+        Some(ResourceCard::Sheep) => Port::Sheep,
+        Some(ResourceCard::Wheat) => Port::Wheat,
+        Some(ResourceCard::Ore) => Port::Ore,
+    }
+}
+
+#[allow(non_snake_case)]
+impl Board {
+    // Writes the tiles, chits and ports of a base board as a Catanatron map. The robber and buildings aren't included.
+    pub fn toCatanatronTiles(&self) -> Result<Vec<CatanatronTileEntry>, String> {
+        if self.edition != Edition::Base {
+            return Err(String::from("Only the base board can be written as a Catanatron map."));
+        }
+
+        let mut entries: Vec<CatanatronTileEntry> = self.tiles.iter().map(|tile| {
+            let hex = HexCoord::fromIndex(tile.index, self.edition).unwrap();
+            let catanatron_tile = match tile.terrain.resource() {
+                Some(resource) => CatanatronTile::ResourceTile { id: tile.index, resource: CatanatronResource::fromResourceCard(resource), number: tile.chit },
+                None => CatanatronTile::Desert { id: tile.index },
+            };
+            CatanatronTileEntry { coordinate: to_cube(hex), tile: catanatron_tile }
+        }).collect();
+
+        // Every port is on a side of a land tile along the coast, and sits on the sea tile across that side.
+        let mut sea_tiles = vec![];
+        for (index, (port, (node1, node2))) in self.ports.iter().zip(&self.port_node_mapping).enumerate() {
+            let edge = board_edges(self.edition).iter().find(|edge| {
+                let nodes = edge.nodes().map(|node| node.toIndex(self.edition));
+                nodes == [Some(*node1), Some(*node2)] || nodes == [Some(*node2), Some(*node1)]
+            }).ok_or(format!("Port {} isn't on a side of a tile.", index))?;
+            let hex = *edge.hexes().iter().find(|hex| !hex.isOnBoard(self.edition)).ok_or(format!("Port {} isn't on the coast.", index))?;
+            let direction = EDGE_REFS[hex.edges().iter().position(|side| side == edge).unwrap()];
+            sea_tiles.push(hex);
+            entries.push(CatanatronTileEntry {
+                coordinate: to_cube(hex),
+                tile: CatanatronTile::Port { id: self.tiles.len() + index, direction, resource: port.resource().map(CatanatronResource::fromResourceCard) },
+            });
+        }
+
+        // The rest of the ring of sea around the board is water.
+        let center = HexCoord::new(0, 0);
+        for r in -3..=3 {
+            for q in -3..=3 {
+                let hex = HexCoord::new(q, r);
+                if hex.distance(&center) == 3 && !sea_tiles.contains(&hex) {
+                    entries.push(CatanatronTileEntry { coordinate: to_cube(hex), tile: CatanatronTile::Water });
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    // Reads an empty base board from a Catanatron map. The robber starts on the desert. Every port has to be at one
+    // of the base board's port positions, see Topology::port_nodes.
+    pub fn fromCatanatronTiles(entries: &[CatanatronTileEntry]) -> Result<Board, String> {
+        let edition = Edition::Base;
+        let topology = topology(edition);
+        let mut tiles: Vec<Option<Tile>> = vec![None; board_tiles(edition).len()];
+        let mut ports: Vec<Option<Port>> = vec![None; topology.port_nodes.len()];
+
+        for entry in entries {
+            let hex = from_cube(entry.coordinate)?;
+            let (terrain, chit) = match &entry.tile {
+                CatanatronTile::ResourceTile { resource, number, .. } => {
+                    let terrain = match resource.toResourceCard() {
+                        ResourceCard::Lumber => Terrain::Forest,
+                        ResourceCard::Brick => Terrain::Hills,
+                        ResourceCard::Sheep => Terrain::Plains,
+                        ResourceCard::Wheat => Terrain::Fields,
+                        ResourceCard::Ore => Terrain::Mountains,
+                    };
+                    if !(2..=12).contains(number) || *number == 7 {
+                        return Err(format!("The tile at {:?} has no valid number.", entry.coordinate));
+                    }
+                    (terrain, *number)
+                },
+                CatanatronTile::Desert { .. } => (Terrain::Desert, 0),
+                CatanatronTile::Port { direction, resource, .. } => {
+                    let edge = edge_from_ref(hex, *direction);
+                    let [node1, node2] = edge.nodes().map(|node| node.toIndex(edition));
+                    let index = topology.port_nodes.iter()
+                        .position(|(port1, port2)| [Some(*port1), Some(*port2)] == [node1, node2] || [Some(*port2), Some(*port1)] == [node1, node2])
+                        .ok_or(format!("The port at {:?} isn't at one of the base board's port positions.", entry.coordinate))?;
+                    if ports[index].replace(port_from_resource(*resource)).is_some() {
+                        return Err(format!("The port at {:?} is at the same position as another port.", entry.coordinate));
+                    }
+                    continue;
+                },
+                CatanatronTile::Water => continue,
+            };
+
+            let Some(index) = hex.toIndex(edition) else {
+                return Err(format!("The land tile at {:?} isn't on the base board.", entry.coordinate));
+            };
+            if tiles[index].replace(Tile::new(index, terrain, chit)).is_some() {
+                return Err(format!("There is more than one tile at {:?}.", entry.coordinate));
+            }
+        }
+
+        let tiles = tiles.into_iter().enumerate()
+            .map(|(index, tile)| tile.ok_or(format!("There is no land tile at {:?}.", to_cube(HexCoord::fromIndex(index, edition).unwrap()))))
+            .collect::<Result<Vec<Tile>, String>>()?;
+        let ports = ports.into_iter().enumerate()
+            .map(|(index, port)| port.ok_or(format!("There is no port at port position {}.", index)))
+            .collect::<Result<Vec<Port>, String>>()?;
+        let robber = Board::firstDesert(&tiles).ok_or(String::from("The map has no desert."))?;
+
+        Ok(Board {
+            edition,
+            nodes: (0..topology.numNodes()).map(Node::new).collect(),
+            edges: (0..topology.numEdges()).map(Edge::new).collect(),
+            ports,
+            tiles,
+            robber,
+            port_node_mapping: topology.port_nodes.clone(),
+        })
+    }
+}
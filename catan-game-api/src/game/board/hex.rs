@@ -0,0 +1,322 @@
+use std::sync::LazyLock;
+use serde::{Serialize, Deserialize};
+
+use crate::game::edition::Edition;
+
+// Every tile, node and edge on the board of an edition, in the order of their indices.
+// Indices go from the top of the board to the bottom, and from left to right within a row.
+struct Layout {
+    tiles: Vec<HexCoord>,
+    nodes: Vec<NodeCoord>,
+    edges: Vec<EdgeCoord>,
+}
+
+// The base board is a hexagon of tiles two rings around the center tile.
+static BASE: LazyLock<Layout> = LazyLock::new(|| {
+    Layout::fromTiles((-2..=2).flat_map(|r| (-2..=2).map(move |q| HexCoord::new(q, r)))
+        .filter(|hex| hex.distance(&HexCoord::new(0, 0)) <= 2)
+        .collect())
+});
+
+// The 5-6 player board has rows of 3, 4, 5, 6, 5, 4 and 3 tiles from the top.
+static FIVE_SIX_PLAYER: LazyLock<Layout> = LazyLock::new(|| {
+    Layout::fromTiles((-3..=3).flat_map(|r| (-2..=3).map(move |q| HexCoord::new(q, r)))
+        .filter(|hex| (-2..=3).contains(&(hex.q + hex.r)))
+        .collect())
+});
+
+fn layout(edition: Edition) -> &'static Layout {
+    match edition {
+        Edition::Base => &BASE,
+        Edition::FiveSixPlayer => &FIVE_SIX_PLAYER,
+    }
+}
+
+#[allow(non_snake_case)]
+impl Layout {
+    fn fromTiles(tiles: Vec<HexCoord>) -> Layout {
+        let mut nodes: Vec<NodeCoord> = tiles.iter().flat_map(|tile| tile.nodes()).collect();
+        nodes.sort_by_key(|node| node.layoutKey());
+        nodes.dedup();
+        let mut edges: Vec<EdgeCoord> = tiles.iter().flat_map(|tile| tile.edges()).collect();
+        edges.sort_by_key(|edge| edge.layoutKey());
+        edges.dedup();
+        Layout { tiles, nodes, edges }
+    }
+}
+
+// The tiles of the board of an edition, in the order of their indices.
+pub fn board_tiles(edition: Edition) -> &'static [HexCoord] {
+    &layout(edition).tiles
+}
+
+pub fn board_nodes(edition: Edition) -> &'static [NodeCoord] {
+    &layout(edition).nodes
+}
+
+pub fn board_edges(edition: Edition) -> &'static [EdgeCoord] {
+    &layout(edition).edges
+}
+
+// The axial coordinates of a tile. The third cube coordinate is s = -q - r.
+// Tiles are pointy-topped, q increases to the east and r increases to the south-east.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct HexCoord {
+    pub q: i32,
+    pub r: i32,
+}
+
+// The corner of a tile a node is at. Every node is the top or bottom corner of exactly one tile,
+// though that tile may be in the sea.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum NodeCorner {
+    North,
+    South,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NodeCoord {
+    pub hex: HexCoord,
+    pub corner: NodeCorner,
+}
+
+// The side of a tile an edge is on. Every edge is the north west, north east or west side of exactly one tile.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EdgeSide {
+    NorthWest,
+    NorthEast,
+    West,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct EdgeCoord {
+    pub hex: HexCoord,
+    pub side: EdgeSide,
+}
+
+#[allow(non_snake_case)]
+impl HexCoord {
+    pub fn new(q: i32, r: i32) -> Self {
+        HexCoord { q, r }
+    }
+
+    pub fn s(&self) -> i32 {
+        -self.q - self.r
+    }
+
+    pub fn fromIndex(index: usize, edition: Edition) -> Option<Self> {
+        board_tiles(edition).get(index).copied()
+    }
+
+    pub fn toIndex(&self, edition: Edition) -> Option<usize> {
+        board_tiles(edition).iter().position(|tile| tile == self)
+    }
+
+    pub fn isOnBoard(&self, edition: Edition) -> bool {
+        self.toIndex(edition).is_some()
+    }
+
+    // The number of steps between two tiles.
+    pub fn distance(&self, other: &HexCoord) -> i32 {
+        ((self.q - other.q).abs() + (self.r - other.r).abs() + (self.s() - other.s()).abs()) / 2
+    }
+
+    // The tiles sharing a side with this one, clockwise from the east.
+    pub fn neighbors(&self) -> [HexCoord; 6] {
+        [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)].map(|(q, r)| HexCoord::new(self.q + q, self.r + r))
+    }
+
+    // The corners of the tile, clockwise from the top.
+    pub fn nodes(&self) -> [NodeCoord; 6] {
+        let (q, r) = (self.q, self.r);
+        [
+            NodeCoord::new(q, r, NodeCorner::North),
+            NodeCoord::new(q + 1, r - 1, NodeCorner::South),
+            NodeCoord::new(q, r + 1, NodeCorner::North),
+            NodeCoord::new(q, r, NodeCorner::South),
+            NodeCoord::new(q - 1, r + 1, NodeCorner::North),
+            NodeCoord::new(q, r - 1, NodeCorner::South),
+        ]
+    }
+
+    // The sides of the tile, clockwise from the north east.
+    pub fn edges(&self) -> [EdgeCoord; 6] {
+        let (q, r) = (self.q, self.r);
+        [
+            EdgeCoord::new(q, r, EdgeSide::NorthEast),
+            EdgeCoord::new(q + 1, r, EdgeSide::West),
+            EdgeCoord::new(q, r + 1, EdgeSide::NorthWest),
+            EdgeCoord::new(q - 1, r + 1, EdgeSide::NorthEast),
+            EdgeCoord::new(q, r, EdgeSide::West),
+            EdgeCoord::new(q, r, EdgeSide::NorthWest),
+        ]
+    }
+
+    // The center of the tile in pixels, where size is the distance from a tile's center to its corners.
+    // The center tile is at the origin, and y increases down the screen.
+    pub fn toPixel(&self, size: f64) -> (f64, f64) {
+        let x = size * 3f64.sqrt() * (self.q as f64 + self.r as f64 / 2.0);
+        let y = size * 1.5 * self.r as f64;
+        (x, y)
+    }
+
+    // The tile containing a pixel, see toPixel.
+    pub fn fromPixel(x: f64, y: f64, size: f64) -> Self {
+        let q = (3f64.sqrt() / 3.0 * x - y / 3.0) / size;
+        let r = (2.0 / 3.0 * y) / size;
+        let s = -q - r;
+
+        // Round to the nearest tile, fixing up whichever coordinate was rounded the most.
+        let (mut rounded_q, mut rounded_r, rounded_s) = (q.round(), r.round(), s.round());
+        let (q_diff, r_diff, s_diff) = ((rounded_q - q).abs(), (rounded_r - r).abs(), (rounded_s - s).abs());
+        if q_diff > r_diff && q_diff > s_diff {
+            rounded_q = -rounded_r - rounded_s;
+        } else if r_diff > s_diff {
+            rounded_r = -rounded_q - rounded_s;
+        }
+        HexCoord::new(rounded_q as i32, rounded_r as i32)
+    }
+}
+
+#[allow(non_snake_case)]
+impl NodeCoord {
+    pub fn new(q: i32, r: i32, corner: NodeCorner) -> Self {
+        NodeCoord { hex: HexCoord::new(q, r), corner }
+    }
+
+    pub fn fromIndex(index: usize, edition: Edition) -> Option<Self> {
+        board_nodes(edition).get(index).copied()
+    }
+
+    pub fn toIndex(&self, edition: Edition) -> Option<usize> {
+        board_nodes(edition).iter().position(|node| node == self)
+    }
+
+    pub fn isOnBoard(&self, edition: Edition) -> bool {
+        self.hexes().iter().any(|hex| hex.isOnBoard(edition))
+    }
+
+    // The three tiles meeting at the node, some of which may be in the sea.
+    pub fn hexes(&self) -> [HexCoord; 3] {
+        let (q, r) = (self.hex.q, self.hex.r);
+        match self.corner {
+            NodeCorner::North => [HexCoord::new(q, r), HexCoord::new(q, r - 1), HexCoord::new(q + 1, r - 1)],
+            NodeCorner::South => [HexCoord::new(q, r), HexCoord::new(q, r + 1), HexCoord::new(q - 1, r + 1)],
+        }
+    }
+
+    // The three edges meeting at the node.
+    pub fn edges(&self) -> [EdgeCoord; 3] {
+        let (q, r) = (self.hex.q, self.hex.r);
+        match self.corner {
+            NodeCorner::North => [
+                EdgeCoord::new(q, r, EdgeSide::NorthWest),
+                EdgeCoord::new(q, r, EdgeSide::NorthEast),
+                EdgeCoord::new(q + 1, r - 1, EdgeSide::West),
+            ],
+            NodeCorner::South => [
+                EdgeCoord::new(q, r + 1, EdgeSide::NorthWest),
+                EdgeCoord::new(q - 1, r + 1, EdgeSide::NorthEast),
+                EdgeCoord::new(q, r + 1, EdgeSide::West),
+            ],
+        }
+    }
+
+    // The three nodes one edge away.
+    pub fn adjacentNodes(&self) -> [NodeCoord; 3] {
+        self.edges().map(|edge| {
+            let [node1, node2] = edge.nodes();
+            if node1 == *self { node2 } else { node1 }
+        })
+    }
+
+    // The position of the node in pixels, see HexCoord::toPixel.
+    pub fn toPixel(&self, size: f64) -> (f64, f64) {
+        let (x, y) = self.hex.toPixel(size);
+        match self.corner {
+            NodeCorner::North => (x, y - size),
+            NodeCorner::South => (x, y + size),
+        }
+    }
+
+    // Orders nodes from top to bottom, then left to right, without rounding errors.
+    pub(crate) fn layoutKey(&self) -> (i32, i32) {
+        let y = 3 * self.hex.r + if self.corner == NodeCorner::North { -2 } else { 2 };
+        (y, 2 * self.hex.q + self.hex.r)
+    }
+}
+
+#[allow(non_snake_case)]
+impl EdgeCoord {
+    pub fn new(q: i32, r: i32, side: EdgeSide) -> Self {
+        EdgeCoord { hex: HexCoord::new(q, r), side }
+    }
+
+    pub fn fromIndex(index: usize, edition: Edition) -> Option<Self> {
+        board_edges(edition).get(index).copied()
+    }
+
+    pub fn toIndex(&self, edition: Edition) -> Option<usize> {
+        board_edges(edition).iter().position(|edge| edge == self)
+    }
+
+    pub fn isOnBoard(&self, edition: Edition) -> bool {
+        self.hexes().iter().any(|hex| hex.isOnBoard(edition))
+    }
+
+    // The two tiles on either side of the edge, one of which may be in the sea.
+    pub fn hexes(&self) -> [HexCoord; 2] {
+        let (q, r) = (self.hex.q, self.hex.r);
+        match self.side {
+            EdgeSide::NorthWest => [HexCoord::new(q, r), HexCoord::new(q, r - 1)],
+            EdgeSide::NorthEast => [HexCoord::new(q, r), HexCoord::new(q + 1, r - 1)],
+            EdgeSide::West => [HexCoord::new(q, r), HexCoord::new(q - 1, r)],
+        }
+    }
+
+    // The two nodes at either end of the edge.
+    pub fn nodes(&self) -> [NodeCoord; 2] {
+        let (q, r) = (self.hex.q, self.hex.r);
+        match self.side {
+            EdgeSide::NorthWest => [NodeCoord::new(q, r, NodeCorner::North), NodeCoord::new(q, r - 1, NodeCorner::South)],
+            EdgeSide::NorthEast => [NodeCoord::new(q, r, NodeCorner::North), NodeCoord::new(q + 1, r - 1, NodeCorner::South)],
+            EdgeSide::West => [NodeCoord::new(q, r - 1, NodeCorner::South), NodeCoord::new(q - 1, r + 1, NodeCorner::North)],
+        }
+    }
+
+    // The middle of the edge in pixels, see HexCoord::toPixel.
+    pub fn toPixel(&self, size: f64) -> (f64, f64) {
+        let [(x1, y1), (x2, y2)] = self.nodes().map(|node| node.toPixel(size));
+        ((x1 + x2) / 2.0, (y1 + y2) / 2.0)
+    }
+
+    pub(crate) fn layoutKey(&self) -> (i32, i32) {
+        let [(y1, x1), (y2, x2)] = self.nodes().map(|node| node.layoutKey());
+        (y1 + y2, x1 + x2)
+    }
+}
+
+// The nodes around a tile, clockwise from the top.
+pub fn tile_nodes(tile: usize, edition: Edition) -> Vec<usize> {
+    HexCoord::fromIndex(tile, edition).map_or(vec![], |hex| hex.nodes().iter().filter_map(|node| node.toIndex(edition)).collect())
+}
+
+// The tiles a node is on.
+pub fn node_tiles(node: usize, edition: Edition) -> Vec<usize> {
+    NodeCoord::fromIndex(node, edition).map_or(vec![], |node| node.hexes().iter().filter_map(|hex| hex.toIndex(edition)).collect())
+}
+
+// The edges touching a node.
+pub fn node_edges(node: usize, edition: Edition) -> Vec<usize> {
+    NodeCoord::fromIndex(node, edition).map_or(vec![], |node| node.edges().iter().filter_map(|edge| edge.toIndex(edition)).collect())
+}
+
+// The nodes one edge away from a node.
+pub fn node_nodes(node: usize, edition: Edition) -> Vec<usize> {
+    NodeCoord::fromIndex(node, edition).map_or(vec![], |node| node.adjacentNodes().iter().filter_map(|node| node.toIndex(edition)).collect())
+}
+
+// The nodes at either end of an edge.
+pub fn edge_nodes(edge: usize, edition: Edition) -> Vec<usize> {
+    EdgeCoord::fromIndex(edge, edition).map_or(vec![], |edge| edge.nodes().iter().filter_map(|node| node.toIndex(edition)).collect())
+}
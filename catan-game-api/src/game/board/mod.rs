@@ -0,0 +1,342 @@
+use rand::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use crate::game::port::Port;
+use crate::game::node::Node;
+use crate::game::edge::Edge;
+use crate::game::tile::Tile;
+use crate::game::building::Building;
+use crate::game::terrain::Terrain;
+use crate::game::chit_layout::ChitLayout;
+use crate::game::edition::Edition;
+use crate::game::action_result::ActionError;
+use crate::game::board::topology::{topology, Topology};
+
+pub mod catanatron;
+pub mod hex;
+pub mod notation;
+pub mod topology;
+
+// The number chits placed on the land tiles, in alphabetical (A to R) order.
+const CHITS: [i32; 18] = [5, 2, 6, 3, 8, 10, 9, 12, 11, 4, 8, 10, 9, 4, 5, 6, 3, 11];
+// The extra chits of the 5-6 player extension, placed after the standard ones.
+const FIVE_SIX_PLAYER_CHITS: [i32; 10] = [2, 5, 4, 6, 3, 9, 8, 11, 10, 12];
+
+// The board is plain data indexed by position, with adjacency read from the edition's topology,
+// so cloning a board gives a fully independent board.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Board {
+    pub edition: Edition,
+    pub ports: Vec<Port>,
+    pub tiles: Vec<Tile>,
+    // The tile the robber is on, which doesn't produce resources.
+    pub robber: usize,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    pub port_node_mapping: Vec<(usize, usize)>,
+}
+
+// The terrain of every tile of an edition, before shuffling.
+fn terrains(edition: Edition) -> Vec<Terrain> {
+    let counts = match edition {
+        Edition::Base => [4, 4, 4, 3, 3, 1],
+        Edition::FiveSixPlayer => [6, 6, 6, 5, 5, 2],
+    };
+    [Terrain::Forest, Terrain::Plains, Terrain::Fields, Terrain::Mountains, Terrain::Hills, Terrain::Desert].into_iter()
+        .zip(counts)
+        .flat_map(|(terrain, count)| std::iter::repeat_n(terrain, count))
+        .collect()
+}
+
+// The ports of an edition, before shuffling.
+fn ports(edition: Edition) -> Vec<Port> {
+    let mut ports = vec![Port::ThreeToOne, Port::ThreeToOne, Port::ThreeToOne, Port::ThreeToOne, Port::Lumber, Port::Ore, Port::Wheat, Port::Sheep, Port::Brick];
+    if edition == Edition::FiveSixPlayer {
+        ports.extend([Port::ThreeToOne, Port::Sheep]);
+    }
+    ports
+}
+
+// The chits of an edition, in the order they are laid in a spiral.
+fn chits(edition: Edition) -> Vec<i32> {
+    match edition {
+        Edition::Base => CHITS.to_vec(),
+        Edition::FiveSixPlayer => CHITS.iter().chain(FIVE_SIX_PLAYER_CHITS.iter()).copied().collect(),
+    }
+}
+
+#[allow(non_snake_case)]
+impl Board {
+    pub fn new(edition: Edition, chit_layout: ChitLayout, rng: &mut impl Rng) -> Self {
+        let topology = topology(edition);
+
+        // Initialize port nodes.
+        let mut ports = ports(edition);
+        ports.shuffle(rng);
+
+        // Initialize Tiles
+        let mut tiles: Vec<Tile> = terrains(edition).into_iter().map(|terrain| Tile::new(0, terrain, 0)).collect();
+        tiles.shuffle(rng);
+        for (i, tile) in tiles.iter_mut().enumerate() {
+            tile.index = i;
+        }
+        let robber = Board::firstDesert(&tiles).unwrap_or(0);
+
+        Board::placeChits(&mut tiles, topology, chits(edition), chit_layout, rng);
+
+        // println!("Successfully created new board.");
+        Board {
+            edition,
+            nodes: (0..topology.numNodes()).map(Node::new).collect(),
+            edges: (0..topology.numEdges()).map(Edge::new).collect(),
+            ports,
+            tiles,
+            robber,
+            port_node_mapping: topology.port_nodes.clone()
+        }
+    }
+
+    // Returns the first desert, where the robber starts. The 5-6 player board has two deserts, but only one robber.
+    pub(crate) fn firstDesert(tiles: &[Tile]) -> Option<usize> {
+        tiles.iter().position(|tile| tile.terrain == Terrain::Desert)
+    }
+
+    // Places the number chits on every tile other than the desert.
+    fn placeChits(tiles: &mut [Tile], topology: &Topology, mut chits: Vec<i32>, chit_layout: ChitLayout, rng: &mut impl Rng) {
+        let land_tiles: Vec<usize> = match chit_layout {
+            ChitLayout::Spiral => {
+                // Start the spiral on a random corner of the board.
+                let corner = rng.gen_range(0..6);
+                let mut spiral: Vec<usize> = vec![];
+                for ring in topology.spiral_rings.iter() {
+                    for i in 0..ring.len() {
+                        spiral.push(ring[(i + corner * ring.len() / 6) % ring.len()]);
+                    }
+                }
+                spiral
+            },
+            _ => (0..tiles.len()).collect()
+        }.into_iter().filter(|tile| tiles[*tile].terrain != Terrain::Desert).collect();
+
+        loop {
+            if chit_layout != ChitLayout::Spiral {
+                chits.shuffle(rng);
+            }
+            for (tile, chit) in land_tiles.iter().zip(chits.iter()) {
+                tiles[*tile].chit = *chit;
+            }
+
+            if chit_layout != ChitLayout::Balanced || !Board::hasAdjacentRedChits(tiles, topology) {
+                return;
+            }
+        }
+    }
+
+    // Returns whether a 6 or 8 chit is on a tile next to another 6 or 8 chit.
+    fn hasAdjacentRedChits(tiles: &[Tile], topology: &Topology) -> bool {
+        let red_tiles: Vec<&[usize; 6]> = tiles.iter()
+            .filter(|tile| tile.chit == 6 || tile.chit == 8)
+            .map(|tile| &topology.tile_nodes[tile.index])
+            .collect();
+
+        // Two tiles are next to each other if they share an edge, which means sharing two nodes.
+        for (i, tile_nodes) in red_tiles.iter().enumerate() {
+            for other_tile_nodes in &red_tiles[i + 1..] {
+                if tile_nodes.iter().filter(|node| other_tile_nodes.contains(node)).count() >= 2 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn topology(&self) -> &'static Topology {
+        topology(self.edition)
+    }
+
+    // Returns the positions of the edges touching a node.
+    pub fn nodeEdges(&self, node: usize) -> &'static [usize] {
+        &self.topology().node_edges[node]
+    }
+
+    // Returns the positions of the nodes at either end of an edge.
+    pub fn edgeNodes(&self, edge: usize) -> [usize; 2] {
+        self.topology().edge_nodes[edge]
+    }
+
+    // Returns the positions of the nodes around a tile.
+    pub fn tileNodes(&self, tile: usize) -> [usize; 6] {
+        self.topology().tile_nodes[tile]
+    }
+
+    // Returns the positions of the tiles a node is on.
+    pub fn nodeTiles(&self, node: usize) -> &'static [usize] {
+        &self.topology().node_tiles[node]
+    }
+
+    pub fn nodeBuilding(&self, node: usize) -> Option<Building> {
+        self.nodes[node].building
+    }
+
+    pub fn edgeBuilding(&self, edge: usize) -> Option<Building> {
+        self.edges[edge].building
+    }
+
+    // Checks whether a road can be placed next to one of the player's roads.
+    pub fn canPlaceRoad(&self, road: &Building) -> Result<(), ActionError> {
+        let Building::Road(position, player) = *road else { return Err(ActionError::InvalidPosition); };
+        if position >= self.edges.len() {
+            return Err(ActionError::InvalidPosition);
+        }
+
+        // Check to make sure no other road is placed on the current road attempted placement.
+        if self.edgeBuilding(position).is_some() {
+            return Err(ActionError::PositionOccupied);
+        }
+
+        // Check the edges most adjacent to the current edge for one of the player's roads.
+        for node in self.edgeNodes(position) {
+            for &edge in self.nodeEdges(node) {
+                if edge == position {
+                    continue;
+                }
+                if let Some(Building::Road(_, play)) = self.edgeBuilding(edge) {
+                    if play == player {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        return Err(ActionError::NotConnected);
+    }
+
+    // Attempts to place a road, returns why the road couldn't be placed if it wasn't.
+    pub fn placeRoad(&mut self, road: Building) -> Result<(), ActionError> {
+        self.canPlaceRoad(&road)?;
+        let Building::Road(position, _) = road else { return Err(ActionError::InvalidPosition); };
+        self.edges[position].building = Some(road);
+        return Ok(());
+    }
+
+    // Checks whether a settlement is at least two edges away from any other settlement or city.
+    fn checkDistanceRule(&self, position: usize) -> Result<(), ActionError> {
+        for &edge in self.nodeEdges(position) {
+            for node in self.edgeNodes(edge) {
+                if node != position && self.nodeBuilding(node).is_some() {
+                    return Err(ActionError::DistanceRule);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // Checks whether a settlement can be placed.
+    pub fn canPlaceSettlement(&self, settlement: &Building) -> Result<(), ActionError> {
+        let Building::Settlement(position, player) = *settlement else { return Err(ActionError::InvalidPosition); };
+        if position >= self.nodes.len() {
+            return Err(ActionError::InvalidPosition);
+        }
+        if self.nodeBuilding(position).is_some() {
+            return Err(ActionError::PositionOccupied);
+        }
+
+        // Check to make sure player is not near any other cities / settlement (1 road away),
+        // and the player has a road to the attempted settlement placement.
+        self.checkDistanceRule(position)?;
+        let has_nearby_road = self.nodeEdges(position).iter().any(|edge| {
+            matches!(self.edgeBuilding(*edge), Some(Building::Road(_, play)) if play == player)
+        });
+        if !has_nearby_road {
+            return Err(ActionError::NotConnected);
+        }
+        return Ok(());
+    }
+
+    // Attempts to place a settlement
+    pub fn placeSettlement(&mut self, settlement: Building) -> Result<(), ActionError> {
+        self.canPlaceSettlement(&settlement)?;
+        let Building::Settlement(position, _) = settlement else { return Err(ActionError::InvalidPosition); };
+        self.nodes[position].building = Some(settlement);
+        return Ok(());
+    }
+
+    // Checks whether a city can be placed, which needs one of the player's settlements on the node.
+    pub fn canPlaceCity(&self, city: &Building) -> Result<(), ActionError> {
+        let Building::City(position, player) = *city else { return Err(ActionError::InvalidPosition); };
+        if position >= self.nodes.len() {
+            return Err(ActionError::InvalidPosition);
+        }
+        match self.nodeBuilding(position) {
+            Some(Building::Settlement(_, play)) if play == player => Ok(()),
+            _ => Err(ActionError::InvalidPosition)
+        }
+    }
+
+    // Attempts to place a city
+    pub fn placeCity(&mut self, city: Building) -> Result<(), ActionError> {
+        self.canPlaceCity(&city)?;
+        let Building::City(position, _) = city else { return Err(ActionError::InvalidPosition); };
+        self.nodes[position].building = Some(city);
+        return Ok(());
+    }
+
+    // Checks whether a beginning settlement can be placed, which doesn't need a road.
+    pub fn canPlaceInitialSettlement(&self, settlement: &Building) -> Result<(), ActionError> {
+        let Building::Settlement(position, _) = *settlement else { return Err(ActionError::InvalidPosition); };
+        if position >= self.nodes.len() {
+            return Err(ActionError::InvalidPosition);
+        }
+        if self.nodeBuilding(position).is_some() {
+            return Err(ActionError::PositionOccupied);
+        }
+        self.checkDistanceRule(position)
+    }
+
+    // Attempts to place a settlement (beginning settlement).
+    pub fn placeInitialSettlement(&mut self, settlement: Building) -> Result<(), ActionError> {
+        self.canPlaceInitialSettlement(&settlement)?;
+        let Building::Settlement(position, _) = settlement else { return Err(ActionError::InvalidPosition); };
+        self.nodes[position].building = Some(settlement);
+        return Ok(());
+    }
+
+    // Checks whether a beginning road can be placed, which must touch the settlement the player just placed.
+    pub fn canPlaceInitialRoad(&self, road: &Building, settlement: usize) -> Result<(), ActionError> {
+        let Building::Road(position, player) = *road else { return Err(ActionError::InvalidPosition); };
+        if position >= self.edges.len() {
+            return Err(ActionError::InvalidPosition);
+        }
+
+        // Ensure the current edge doesn't have a road.
+        if self.edgeBuilding(position).is_some() {
+            return Err(ActionError::PositionOccupied);
+        }
+
+        // Ensure the road touches the player's new settlement.
+        let touches_settlement = self.edgeNodes(position).contains(&settlement)
+            && matches!(self.nodeBuilding(settlement), Some(Building::Settlement(_, owner)) if owner == player);
+        if !touches_settlement {
+            return Err(ActionError::NotConnected);
+        }
+        return Ok(());
+    }
+
+    // Attempts to place a road (initial road).
+    pub fn placeInitialRoad(&mut self, road: Building, settlement: usize) -> Result<(), ActionError> {
+        self.canPlaceInitialRoad(&road, settlement)?;
+        let Building::Road(position, _) = road else { return Err(ActionError::InvalidPosition); };
+        self.edges[position].building = Some(road);
+        return Ok(());
+    }
+
+    // Removes a road from the board.
+    pub fn removeRoad(&mut self, road: Building) -> bool {
+        match road {
+            Building::Road(pos, _) => {
+                self.edges[pos].building = None;
+                return true;
+            },
+            _ => { return false; }
+        }
+    }
+}
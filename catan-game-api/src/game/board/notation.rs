@@ -0,0 +1,140 @@
+use crate::game::board::Board;
+use crate::game::board::topology::topology;
+use crate::game::edition::Edition;
+use crate::game::edge::Edge;
+use crate::game::node::Node;
+use crate::game::port::Port;
+use crate::game::terrain::Terrain;
+use crate::game::tile::Tile;
+
+// A board string is a short way to write a board, listing the tiles in board order, then the ports in port order,
+// split by a '|'. Boards from Catanatron are read and written as its map JSON instead, see board::catanatron.
+// Each tile is the letter of the resource it produces followed by its chit, with '*' marking the robber:
+// L lumber, B brick, S sheep, W wheat, O ore and D for the desert, which has no chit.
+// Each port is the letter of the resource it trades, or 3 for a three to one port.
+// A board string with 30 tiles and 11 ports is a 5-6 player board.
+// For example "O10,S2,L9,W12,B6,S4,B10,W9,L11,D*,L3,O8,L8,O3,W4,S5,B5,W6,S11|3S3WB3LO3".
+
+fn terrain_letter(terrain: Terrain) -> char {
+    match terrain {
+        Terrain::Forest => 'L',
+        Terrain::Hills => 'B',
+        Terrain::Plains => 'S',
+        Terrain::Fields => 'W',
+        Terrain::Mountains => 'O',
+        Terrain::Desert => 'D',
+    }
+}
+
+fn terrain_from_letter(letter: char) -> Option<Terrain> {
+    match letter {
+        'L' => Some(Terrain::Forest),
+        'B' => Some(Terrain::Hills),
+        'S' => Some(Terrain::Plains),
+        'W' => Some(Terrain::Fields),
+        'O' => Some(Terrain::Mountains),
+        'D' => Some(Terrain::Desert),
+        _ => None
+    }
+}
+
+fn port_letter(port: Port) -> char {
+    match port {
+        Port::ThreeToOne => '3',
+        Port::Lumber => 'L',
+        Port::Brick => 'B',
+        Port::Sheep => 'S',
+        Port::Wheat => 'W',
+        Port::Ore => 'O',
+    }
+}
+
+fn port_from_letter(letter: char) -> Option<Port> {
+    match letter {
+        '3' => Some(Port::ThreeToOne),
+        'L' => Some(Port::Lumber),
+        'B' => Some(Port::Brick),
+        'S' => Some(Port::Sheep),
+        'W' => Some(Port::Wheat),
+        'O' => Some(Port::Ore),
+        _ => None
+    }
+}
+
+#[allow(non_snake_case)]
+impl Board {
+    // Writes the tiles, chits, robber and ports of the board as a board string. Buildings aren't included.
+    pub fn toBoardString(&self) -> String {
+        let tiles: Vec<String> = self.tiles.iter().map(|tile| {
+            let chit = if tile.terrain == Terrain::Desert { String::new() } else { tile.chit.to_string() };
+            let robber = if tile.index == self.robber { "*" } else { "" };
+            format!("{}{}{}", terrain_letter(tile.terrain), chit, robber)
+        }).collect();
+        let ports: String = self.ports.iter().map(|port| port_letter(*port)).collect();
+        format!("{}|{}", tiles.join(","), ports)
+    }
+
+    // Reads an empty board from a board string. Without a '*', the robber starts on the desert.
+    pub fn fromBoardString(board_string: &str) -> Result<Board, String> {
+        let Some((tiles_string, ports_string)) = board_string.trim().split_once('|') else {
+            return Err(String::from("The board string has no '|' between the tiles and ports."));
+        };
+
+        let mut tiles = vec![];
+        let mut robbers = vec![];
+        for (index, token) in tiles_string.split(',').map(str::trim).enumerate() {
+            let mut letters = token.chars();
+            let Some(terrain) = letters.next().and_then(terrain_from_letter) else {
+                return Err(format!("Tile {} has no valid terrain in '{}'.", index, token));
+            };
+            let rest = letters.as_str();
+            let chit = match rest.strip_suffix('*') {
+                Some(chit) => {
+                    robbers.push(index);
+                    chit
+                },
+                None => rest
+            };
+
+            let chit = match (terrain, chit) {
+                (Terrain::Desert, "") => 0,
+                (Terrain::Desert, _) => return Err(format!("Tile {} is a desert with a chit.", index)),
+                (_, chit) => match chit.parse::<i32>() {
+                    Ok(chit) if (2..=12).contains(&chit) && chit != 7 => chit,
+                    _ => return Err(format!("Tile {} has no valid chit in '{}'.", index, token))
+                }
+            };
+            tiles.push(Tile { index, terrain, chit });
+        }
+        // The number of tiles tells which board it is.
+        let edition = match tiles.len() {
+            19 => Edition::Base,
+            30 => Edition::FiveSixPlayer,
+            num_tiles => return Err(format!("The board string has {} tiles instead of 19 or 30.", num_tiles))
+        };
+        let topology = topology(edition);
+
+        let robber = match robbers[..] {
+            [] => Board::firstDesert(&tiles).ok_or(String::from("The board string has no desert or robber."))?,
+            [robber] => robber,
+            _ => return Err(String::from("The board string has more than one robber."))
+        };
+
+        let ports = ports_string.trim().chars()
+            .map(|letter| port_from_letter(letter).ok_or(format!("'{}' is not a valid port.", letter)))
+            .collect::<Result<Vec<Port>, String>>()?;
+        if ports.len() != topology.port_nodes.len() {
+            return Err(format!("The board string has {} ports instead of {}.", ports.len(), topology.port_nodes.len()));
+        }
+
+        Ok(Board {
+            edition,
+            nodes: (0..topology.numNodes()).map(Node::new).collect(),
+            edges: (0..topology.numEdges()).map(Edge::new).collect(),
+            ports,
+            tiles,
+            robber,
+            port_node_mapping: topology.port_nodes.clone(),
+        })
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::sync::LazyLock;
+
+use crate::game::board::hex::{self, HexCoord, NodeCoord, EdgeCoord};
+use crate::game::edition::Edition;
+
+// The outer ring of tiles, counter-clockwise from the top left corner.
+const SPIRAL_OUTER_RING: [usize; 12] = [0, 3, 7, 12, 16, 17, 18, 15, 11, 6, 2, 1];
+// The inner ring of tiles, counter-clockwise from the tile below the top left corner.
+const SPIRAL_INNER_RING: [usize; 6] = [4, 8, 13, 14, 10, 5];
+const CENTER_TILE: usize = 9;
+
+// The edges touching each node.
+const NODE_EDGES: [&[usize]; 54] = [
+    &[0, 1], &[2, 3], &[4, 5], &[0, 6], &[1, 2, 7], &[3, 4, 8],
+    &[5, 9], &[6, 10, 11], &[7, 12, 13], &[8, 14, 15], &[9, 16, 17], &[10, 18],
+    &[11, 12, 19], &[13, 14, 20], &[15, 16, 21], &[17, 22], &[18, 23, 24], &[19, 25, 26],
+    &[20, 27, 28], &[21, 29, 30], &[22, 31, 32], &[23, 33], &[24, 25, 34], &[26, 27, 35],
+    &[28, 29, 36], &[30, 31, 37], &[32, 38], &[33, 39], &[34, 40, 41], &[35, 42, 43],
+    &[36, 44, 45], &[37, 46, 47], &[38, 48], &[39, 40, 49], &[41, 42, 50], &[43, 44, 51],
+    &[45, 46, 52], &[47, 48, 53], &[49, 54], &[50, 55, 56], &[51, 57, 58], &[52, 59, 60],
+    &[53, 61], &[54, 55, 62], &[56, 57, 63], &[58, 59, 64], &[60, 61, 65], &[62, 66],
+    &[63, 67, 68], &[64, 69, 70], &[65, 71], &[66, 67], &[68, 69], &[70, 71],
+];
+
+// The nodes around each tile. Tiles are in rows of 3, 4, 5, 4 and 3 from the top of the board.
+const TILE_NODES: [[usize; 6]; 19] = [
+    [0, 3, 4, 7, 8, 12],
+    [1, 4, 5, 8, 9, 13],
+    [2, 5, 6, 9, 10, 14],
+    [7, 11, 12, 16, 17, 22],
+    [8, 12, 13, 17, 18, 23],
+    [9, 13, 14, 18, 19, 24],
+    [10, 14, 15, 19, 20, 25],
+    [16, 21, 22, 27, 28, 33],
+    [17, 22, 23, 28, 29, 34],
+    [18, 23, 24, 29, 30, 35],
+    [19, 24, 25, 30, 31, 36],
+    [20, 25, 26, 31, 32, 37],
+    [28, 33, 34, 38, 39, 43],
+    [29, 34, 35, 39, 40, 44],
+    [30, 35, 36, 40, 41, 45],
+    [31, 36, 37, 41, 42, 46],
+    [39, 43, 44, 47, 48, 51],
+    [40, 44, 45, 48, 49, 52],
+    [41, 45, 46, 49, 50, 53],
+];
+
+// The pairs of nodes each port can be used from.
+const PORT_NODES: [(usize, usize); 9] = [(0, 3), (1, 5), (10, 15), (11, 16), (26, 32), (33, 38), (42, 46), (47, 51), (49, 52)];
+
+// The pairs of nodes each port of the 5-6 player board can be used from, clockwise around the coast from the top left.
+// The top of the board has the same ports as the base board, and like the base board the ports are three or four
+// coast edges apart.
+const FIVE_SIX_PLAYER_PORT_NODES: [(usize, usize); 11] = [
+    (11, 16), (0, 3), (1, 5), (10, 15), (26, 32), (52, 58), (68, 72), (75, 78), (73, 77), (53, 59), (33, 40),
+];
+
+static BASE: LazyLock<Topology> = LazyLock::new(|| {
+    Topology::fromTables(
+        NODE_EDGES.iter().map(|edges| edges.to_vec()).collect(),
+        TILE_NODES.to_vec(),
+        PORT_NODES.to_vec(),
+        vec![SPIRAL_OUTER_RING.to_vec(), SPIRAL_INNER_RING.to_vec(), vec![CENTER_TILE]],
+    ).expect("The built-in board topology is invalid.")
+});
+
+static FIVE_SIX_PLAYER: LazyLock<Topology> = LazyLock::new(|| {
+    five_six_player_topology().expect("The 5-6 player board topology is invalid.")
+});
+
+// How the tiles, nodes and edges of a board connect. Every board of an edition shares the same topology.
+pub struct Topology {
+    pub tile_nodes: Vec<[usize; 6]>,
+    pub node_tiles: Vec<Vec<usize>>,
+    pub node_edges: Vec<Vec<usize>>,
+    pub edge_nodes: Vec<[usize; 2]>,
+    pub port_nodes: Vec<(usize, usize)>,
+    // The rings of tiles from the outside in, each counter-clockwise from the top left, for the spiral chit layout.
+    pub spiral_rings: Vec<Vec<usize>>,
+}
+
+// Returns the topology of the board for an edition.
+pub fn topology(edition: Edition) -> &'static Topology {
+    match edition {
+        Edition::Base => &BASE,
+        Edition::FiveSixPlayer => &FIVE_SIX_PLAYER,
+    }
+}
+
+#[allow(non_snake_case)]
+impl Topology {
+    // Builds a topology from the edges at each node and the nodes around each tile, checking they describe a board:
+    // every edge joins two nodes, every node touches two or three edges and one to three tiles, and every port is on the coast.
+    pub fn fromTables(node_edges: Vec<Vec<usize>>, tile_nodes: Vec<[usize; 6]>, port_nodes: Vec<(usize, usize)>, spiral_rings: Vec<Vec<usize>>) -> Result<Topology, String> {
+        let num_edges = node_edges.iter().flatten().max().map_or(0, |edge| edge + 1);
+        let mut edge_nodes: Vec<Vec<usize>> = vec![vec![]; num_edges];
+        for (node, edges) in node_edges.iter().enumerate() {
+            if edges.len() < 2 || edges.len() > 3 {
+                return Err(format!("Node {} touches {} edges.", node, edges.len()));
+            }
+            for edge in edges.iter() {
+                edge_nodes[*edge].push(node);
+            }
+        }
+        if let Some(edge) = edge_nodes.iter().position(|nodes| nodes.len() != 2) {
+            return Err(format!("Edge {} joins {} nodes.", edge, edge_nodes[edge].len()));
+        }
+
+        let mut node_tiles: Vec<Vec<usize>> = vec![vec![]; node_edges.len()];
+        for (tile, nodes) in tile_nodes.iter().enumerate() {
+            for (i, node) in nodes.iter().enumerate() {
+                if *node >= node_edges.len() || nodes[..i].contains(node) {
+                    return Err(format!("Tile {} has an invalid or repeated node {}.", tile, node));
+                }
+                node_tiles[*node].push(tile);
+            }
+        }
+        if let Some(node) = node_tiles.iter().position(|tiles| tiles.is_empty() || tiles.len() > 3) {
+            return Err(format!("Node {} is on {} tiles.", node, node_tiles[node].len()));
+        }
+
+        for (node1, node2) in port_nodes.iter().copied() {
+            let joined = edge_nodes.iter().any(|nodes| nodes.contains(&node1) && nodes.contains(&node2));
+            if !joined || node_tiles[node1].len() == 3 || node_tiles[node2].len() == 3 {
+                return Err(format!("The port on nodes {} and {} is not on the coast.", node1, node2));
+            }
+        }
+
+        let mut spiral_tiles: Vec<usize> = spiral_rings.iter().flatten().copied().collect();
+        spiral_tiles.sort();
+        if spiral_tiles != (0..tile_nodes.len()).collect::<Vec<usize>>() {
+            return Err(String::from("The spiral doesn't go through every tile once."));
+        }
+
+        Ok(Topology {
+            tile_nodes,
+            node_tiles,
+            node_edges,
+            edge_nodes: edge_nodes.into_iter().map(|nodes| [nodes[0], nodes[1]]).collect(),
+            port_nodes,
+            spiral_rings,
+        })
+    }
+
+    pub fn numTiles(&self) -> usize {
+        self.tile_nodes.len()
+    }
+
+    pub fn numNodes(&self) -> usize {
+        self.node_edges.len()
+    }
+
+    pub fn numEdges(&self) -> usize {
+        self.edge_nodes.len()
+    }
+}
+
+// Builds the 5-6 player board from hex coordinates, see hex::board_tiles. Tiles, nodes and edges are indexed
+// from the top of the board to the bottom and left to right, like the base board.
+fn five_six_player_topology() -> Result<Topology, String> {
+    let tiles = hex::board_tiles(Edition::FiveSixPlayer);
+    let nodes = hex::board_nodes(Edition::FiveSixPlayer);
+    let edges = hex::board_edges(Edition::FiveSixPlayer);
+
+    let node_indices: HashMap<NodeCoord, usize> = nodes.iter().enumerate().map(|(index, node)| (*node, index)).collect();
+    let edge_indices: HashMap<EdgeCoord, usize> = edges.iter().enumerate().map(|(index, edge)| (*edge, index)).collect();
+
+    let tile_nodes = tiles.iter().map(|tile| tile.nodes().map(|node| node_indices[&node])).collect();
+    let node_edges = nodes.iter()
+        .map(|node| node.edges().iter().filter_map(|edge| edge_indices.get(edge).copied()).collect())
+        .collect();
+
+    // Peel rings of tiles off the outside of the board for the spiral.
+    let mut spiral_rings = vec![];
+    let mut remaining: Vec<HexCoord> = tiles.to_vec();
+    while !remaining.is_empty() {
+        let (mut ring, inner): (Vec<HexCoord>, Vec<HexCoord>) = remaining.iter().partition(|tile| {
+            tile.neighbors().iter().any(|neighbor| !remaining.contains(neighbor))
+        });
+        let center = center_of(&ring);
+        let start = angle_of(ring[0].toPixel(1.0), center);
+        ring.sort_by(|tile1, tile2| {
+            let counter_clockwise = |tile: &HexCoord| (start - angle_of(tile.toPixel(1.0), center)).rem_euclid(TAU);
+            counter_clockwise(tile1).total_cmp(&counter_clockwise(tile2))
+        });
+        spiral_rings.push(ring.iter().map(|tile| tiles.iter().position(|other| other == tile).unwrap()).collect());
+        remaining = inner;
+    }
+
+    Topology::fromTables(node_edges, tile_nodes, FIVE_SIX_PLAYER_PORT_NODES.to_vec(), spiral_rings)
+}
+
+// The average position of the tiles, in pixels of size 1.
+fn center_of(tiles: &[HexCoord]) -> (f64, f64) {
+    let (x, y) = tiles.iter().map(|tile| tile.toPixel(1.0)).fold((0.0, 0.0), |(x, y), (tile_x, tile_y)| (x + tile_x, y + tile_y));
+    (x / tiles.len() as f64, y / tiles.len() as f64)
+}
+
+// The clockwise angle of a position around the center, since y increases down the screen.
+fn angle_of((x, y): (f64, f64), (center_x, center_y): (f64, f64)) -> f64 {
+    (y - center_y).atan2(x - center_x)
+}
@@ -1,7 +1,7 @@
 use serde::{Serialize, Deserialize};
 
 // (Position, Player)
-#[derive(Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
 pub enum Building {
     Settlement(usize, usize),
     City(usize, usize),
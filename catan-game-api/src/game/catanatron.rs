@@ -0,0 +1,423 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+
+use crate::game::action::Action;
+use crate::game::board::Board;
+use crate::game::board::catanatron::{edge_to_ref, from_cube, node_from_ref, node_to_ref, to_cube, CatanatronEdgeRef, CatanatronNodeRef, CatanatronResource, CatanatronTileEntry};
+use crate::game::board::hex::{EdgeCoord, HexCoord, NodeCoord};
+use crate::game::building::Building;
+use crate::game::development::DevelopmentCard;
+use crate::game::edition::Edition;
+use crate::game::event::Event;
+use crate::game::game::Game;
+use crate::game::record::{GameRecord, RECORD_FORMAT_VERSION};
+use crate::game::replay::ReplayError;
+use crate::game::resource::ResourceCard;
+
+// A game as Catanatron writes it as JSON. Only what is needed to play the game back is read, and anything else,
+// such as each player's state, is ignored. The map is in board::catanatron. Each node has an id, and the nodes table
+// gives the tile and corner of every id, so a game written here numbers the nodes as this board does.
+// Each action is [color, action type, value], where the value holds what happened, such as the dice rolled:
+// ["RED","ROLL",[3,4]], ["RED","BUILD_ROAD",[3,12]], ["BLUE","MOVE_ROBBER",[[0,1,-1],"RED","WHEAT"]],
+// ["BLUE","BUY_DEVELOPMENT_CARD","KNIGHT"] or ["RED","MARITIME_TRADE",["WOOD","WOOD","WOOD","WOOD","ORE"]].
+// Catanatron has no 5-6 player games, and trades between players and undone actions can't be written.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CatanatronGame {
+    pub tiles: Vec<CatanatronTileEntry>,
+    pub nodes: BTreeMap<usize, CatanatronNode>,
+    pub edges: Vec<CatanatronEdge>,
+    pub actions: Vec<CatanatronAction>,
+    // The players' colors, in seat order.
+    pub colors: Vec<CatanatronColor>,
+    pub robber_coordinate: [i32; 3],
+    #[serde(default)]
+    pub winning_color: Option<CatanatronColor>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CatanatronNode {
+    pub id: usize,
+    pub tile_coordinate: [i32; 3],
+    pub direction: CatanatronNodeRef,
+    #[serde(default)]
+    pub building: Option<CatanatronBuilding>,
+    #[serde(default)]
+    pub color: Option<CatanatronColor>,
+}
+
+// An edge, whose id is the ids of the nodes at either end.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CatanatronEdge {
+    pub id: [usize; 2],
+    pub tile_coordinate: [i32; 3],
+    pub direction: CatanatronEdgeRef,
+    #[serde(default)]
+    pub color: Option<CatanatronColor>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CatanatronAction(pub CatanatronColor, pub CatanatronActionType, pub serde_json::Value);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronColor {
+    Red,
+    Blue,
+    Orange,
+    White,
+}
+
+const COLORS: [CatanatronColor; 4] = [CatanatronColor::Red, CatanatronColor::Blue, CatanatronColor::Orange, CatanatronColor::White];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronBuilding {
+    Settlement,
+    City,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronActionType {
+    Roll,
+    MoveRobber,
+    Discard,
+    BuildRoad,
+    BuildSettlement,
+    BuildCity,
+    BuyDevelopmentCard,
+    PlayKnightCard,
+    PlayYearOfPlenty,
+    PlayMonopoly,
+    PlayRoadBuilding,
+    MaritimeTrade,
+    OfferTrade,
+    AcceptTrade,
+    RejectTrade,
+    ConfirmTrade,
+    CancelTrade,
+    EndTurn,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CatanatronDevelopmentCard {
+    Knight,
+    YearOfPlenty,
+    Monopoly,
+    RoadBuilding,
+    VictoryPoint,
+}
+
+#[allow(non_snake_case)]
+impl CatanatronDevelopmentCard {
+    fn fromDevelopmentCard(development_card: DevelopmentCard) -> Self {
+        match development_card {
+            DevelopmentCard::Knight => CatanatronDevelopmentCard::Knight,
+            DevelopmentCard::YearOfPlenty => CatanatronDevelopmentCard::YearOfPlenty,
+            DevelopmentCard::Monopoly => CatanatronDevelopmentCard::Monopoly,
+            DevelopmentCard::RoadBuilding => CatanatronDevelopmentCard::RoadBuilding,
+            DevelopmentCard::VictoryPoint => CatanatronDevelopmentCard::VictoryPoint,
+        }
+    }
+
+    fn toDevelopmentCard(&self) -> DevelopmentCard {
+        match self {
+            CatanatronDevelopmentCard::Knight => DevelopmentCard::Knight,
+            CatanatronDevelopmentCard::YearOfPlenty => DevelopmentCard::YearOfPlenty,
+            CatanatronDevelopmentCard::Monopoly => DevelopmentCard::Monopoly,
+            CatanatronDevelopmentCard::RoadBuilding => DevelopmentCard::RoadBuilding,
+            CatanatronDevelopmentCard::VictoryPoint => DevelopmentCard::VictoryPoint,
+        }
+    }
+}
+
+// Counts the resources in a list of cards, such as the cards given in a maritime trade.
+fn count_resources(resources: &[CatanatronResource]) -> HashMap<ResourceCard, usize> {
+    let mut counts = HashMap::new();
+    for resource in resources {
+        *counts.entry(resource.toResourceCard()).or_insert(0) += 1;
+    }
+    counts
+}
+
+// Lists every card in a hand of resources, in the order of ResourceCard::ALL.
+fn list_resources(resources: &HashMap<ResourceCard, usize>) -> Vec<CatanatronResource> {
+    ResourceCard::ALL.iter()
+        .flat_map(|resource| std::iter::repeat_n(CatanatronResource::fromResourceCard(*resource), resources.get(resource).copied().unwrap_or(0)))
+        .collect()
+}
+
+// Reads the value of the action at the index.
+fn read<T: DeserializeOwned>(index: usize, action: &CatanatronAction) -> Result<T, ReplayError> {
+    serde_json::from_value(action.2.clone()).map_err(|error| ReplayError::UnsupportedAction { index, message: error.to_string() })
+}
+
+// Sorts every run of discards by the key, since Catanatron has players discard in seat order while a game here starts
+// with the player who rolled. The key is given the actions before the run and the discard.
+fn sort_discards<T>(actions: &mut [T], is_discard: impl Fn(&T) -> bool, key: impl Fn(&[T], &T) -> usize) {
+    let mut start = 0;
+    while start < actions.len() {
+        if !is_discard(&actions[start]) {
+            start += 1;
+            continue;
+        }
+        let end = start + actions[start..].iter().take_while(|action| is_discard(action)).count();
+        let (before, run) = actions.split_at_mut(start);
+        let before: &[T] = before;
+        run[..end - start].sort_by_key(|action| key(before, action));
+        start = end;
+    }
+}
+
+#[allow(non_snake_case)]
+impl CatanatronGame {
+    // Writes a base game of up to four players, with the colors given to the players in seat order.
+    pub fn fromGame(game: &Game) -> Result<CatanatronGame, String> {
+        let record = game.toRecord();
+        if record.num_players > COLORS.len() {
+            return Err(format!("Catanatron games have at most {} players.", COLORS.len()));
+        }
+        let edition = Edition::Base;
+        let view = game.view(None);
+        let board = view.board;
+        let tiles = Board::fromBoardString(&record.board)?.toCatanatronTiles()?;
+
+        let nodes = (0..board.nodes.len()).map(|node| {
+            let (hex, direction) = node_to_ref(NodeCoord::fromIndex(node, edition).unwrap());
+            let (building, color) = match board.nodeBuilding(node) {
+                Some(Building::Settlement(_, player_id)) => (Some(CatanatronBuilding::Settlement), Some(COLORS[player_id])),
+                Some(Building::City(_, player_id)) => (Some(CatanatronBuilding::City), Some(COLORS[player_id])),
+                _ => (None, None),
+            };
+            (node, CatanatronNode { id: node, tile_coordinate: to_cube(hex), direction, building, color })
+        }).collect();
+        let edges = (0..board.edges.len()).map(|edge| {
+            let (hex, direction) = edge_to_ref(EdgeCoord::fromIndex(edge, edition).unwrap());
+            let mut id = board.edgeNodes(edge);
+            id.sort();
+            let color = match board.edgeBuilding(edge) {
+                Some(Building::Road(_, player_id)) => Some(COLORS[player_id]),
+                _ => None,
+            };
+            CatanatronEdge { id, tile_coordinate: to_cube(hex), direction, color }
+        }).collect();
+
+        // The random outcome of an action follows it in the events.
+        let outcome = |index: usize| record.events.get(index + 1);
+        let robber_value = |index: usize, tile: usize, victim: Option<usize>| {
+            let resource = match outcome(index) {
+                Some(Event::CardStolen { resource, .. }) => Some(CatanatronResource::fromResourceCard(*resource)),
+                _ => None,
+            };
+            serde_json::json!([to_cube(HexCoord::fromIndex(tile, edition).unwrap()), victim.map(|victim| COLORS[victim]), resource])
+        };
+
+        let mut actions = vec![];
+        for (index, event) in record.events.iter().enumerate() {
+            let (player_id, action) = match event {
+                Event::ActionTaken { player_id, action } => (*player_id, action),
+                Event::ActionUndone | Event::ActionRedone => return Err(String::from("A game with undone actions can't be written as a Catanatron game.")),
+                _ => continue,
+            };
+            let color = COLORS[player_id];
+            let (action_type, value) = match action {
+                Action::RollDice => match outcome(index) {
+                    Some(Event::DiceRolled { dice, .. }) => (CatanatronActionType::Roll, serde_json::json!(dice)),
+                    _ => return Err(format!("The dice roll at event {} has no outcome.", index)),
+                },
+                Action::MoveRobber { tile, victim } => (CatanatronActionType::MoveRobber, robber_value(index, *tile, *victim)),
+                Action::PlayKnight { tile, victim } => {
+                    actions.push(CatanatronAction(color, CatanatronActionType::PlayKnightCard, serde_json::Value::Null));
+                    (CatanatronActionType::MoveRobber, robber_value(index, *tile, *victim))
+                },
+                Action::BuildSettlement { node } => (CatanatronActionType::BuildSettlement, serde_json::json!(node)),
+                Action::BuildCity { node } => (CatanatronActionType::BuildCity, serde_json::json!(node)),
+                Action::BuildRoad { edge } => {
+                    let mut nodes = board.edgeNodes(*edge);
+                    nodes.sort();
+                    (CatanatronActionType::BuildRoad, serde_json::json!(nodes))
+                },
+                Action::BuyDevelopmentCard => match outcome(index) {
+                    Some(Event::DevelopmentCardDrawn { development_card, .. }) => {
+                        (CatanatronActionType::BuyDevelopmentCard, serde_json::json!(CatanatronDevelopmentCard::fromDevelopmentCard(*development_card)))
+                    },
+                    _ => return Err(format!("The development card bought at event {} has no outcome.", index)),
+                },
+                Action::PlayRoadBuilding => (CatanatronActionType::PlayRoadBuilding, serde_json::Value::Null),
+                Action::PlayMonopoly { resource } => (CatanatronActionType::PlayMonopoly, serde_json::json!(CatanatronResource::fromResourceCard(*resource))),
+                Action::PlayYearOfPlenty { first_resource, second_resource } => {
+                    let resources = [first_resource, second_resource].map(|resource| CatanatronResource::fromResourceCard(*resource));
+                    (CatanatronActionType::PlayYearOfPlenty, serde_json::json!(resources))
+                },
+                // Catanatron trades one kind of resource for a single card at a time.
+                Action::MaritimeTrade { giving, receiving } => {
+                    let mut cards: Vec<Option<CatanatronResource>> = list_resources(giving).into_iter().map(Some).collect();
+                    let receiving = list_resources(receiving);
+                    let giving_kinds = giving.values().filter(|amount| **amount > 0).count();
+                    if giving_kinds != 1 || cards.len() > 4 || receiving.len() != 1 {
+                        return Err(format!("The maritime trade at event {} trades more than one batch, which Catanatron can't write.", index));
+                    }
+                    cards.resize(4, None);
+                    cards.push(Some(receiving[0]));
+                    (CatanatronActionType::MaritimeTrade, serde_json::json!(cards))
+                },
+                Action::Discard { resources } => (CatanatronActionType::Discard, serde_json::json!(list_resources(resources))),
+                Action::EndTurn => (CatanatronActionType::EndTurn, serde_json::Value::Null),
+                Action::OfferTrade { .. } | Action::AcceptTrade { .. } | Action::DeclineTrade { .. } | Action::CounterTrade { .. } => {
+                    return Err(String::from("A game with trades between players can't be written as a Catanatron game."));
+                },
+            };
+            actions.push(CatanatronAction(color, action_type, value));
+        }
+        sort_discards(&mut actions, |action| action.1 == CatanatronActionType::Discard, |_, action| COLORS.iter().position(|color| *color == action.0).unwrap());
+
+        Ok(CatanatronGame {
+            tiles,
+            nodes,
+            edges,
+            actions,
+            colors: COLORS[..record.num_players].to_vec(),
+            robber_coordinate: to_cube(HexCoord::fromIndex(board.robber, edition).unwrap()),
+            winning_color: game.winner.map(|winner| COLORS[winner]),
+        })
+    }
+
+    // Converts the game to a record, with the node ids and colors replaced by this game's node indices and player ids.
+    pub fn toRecord(&self) -> Result<GameRecord, ReplayError> {
+        let edition = Edition::Base;
+        let board = Board::fromCatanatronTiles(&self.tiles).map_err(ReplayError::InvalidSetup)?;
+        let num_players = self.colors.len();
+
+        let mut node_indices = HashMap::new();
+        for (id, node) in &self.nodes {
+            let hex = from_cube(node.tile_coordinate).map_err(ReplayError::InvalidSetup)?;
+            let index = node_from_ref(hex, node.direction).toIndex(edition)
+                .ok_or(ReplayError::InvalidSetup(format!("Node {} isn't on the board.", id)))?;
+            node_indices.insert(*id, index);
+        }
+
+        let unsupported = |index: usize, message: String| ReplayError::UnsupportedAction { index, message };
+        let player = |index: usize, color: CatanatronColor| {
+            self.colors.iter().position(|seat| *seat == color).ok_or(unsupported(index, format!("{:?} isn't one of the players.", color)))
+        };
+        let node = |index: usize, id: usize| node_indices.get(&id).copied().ok_or(unsupported(index, format!("Node {} isn't in the nodes table.", id)));
+        let edge = |index: usize, [id1, id2]: [usize; 2]| {
+            let nodes = [node(index, id1)?, node(index, id2)?];
+            (0..board.edges.len()).find(|edge| {
+                let edge_nodes = board.edgeNodes(*edge);
+                edge_nodes == nodes || edge_nodes == [nodes[1], nodes[0]]
+            }).ok_or(unsupported(index, format!("Nodes {} and {} aren't joined by an edge.", id1, id2)))
+        };
+        let tile = |index: usize, coordinate: [i32; 3]| {
+            from_cube(coordinate).ok().and_then(|hex| hex.toIndex(edition)).ok_or(unsupported(index, format!("{:?} isn't a land tile.", coordinate)))
+        };
+
+        let mut actions: Vec<(usize, &CatanatronAction)> = self.actions.iter().enumerate().collect();
+        sort_discards(&mut actions, |(_, action)| action.1 == CatanatronActionType::Discard, |before, (_, action)| {
+            let roller = before.iter().rev().find(|(_, action)| action.1 == CatanatronActionType::Roll).map_or(0, |(_, roll)| player(0, roll.0).unwrap_or(0));
+            (player(0, action.0).unwrap_or(0) + num_players - roller) % num_players
+        });
+
+        let mut events = vec![];
+        let mut actions = actions.into_iter().peekable();
+        while let Some((index, action)) = actions.next() {
+            let player_id = player(index, action.0)?;
+            let mut outcomes = vec![];
+            let taken = match action.1 {
+                CatanatronActionType::Roll => {
+                    let dice: [usize; 2] = read(index, action)?;
+                    outcomes.push(Event::DiceRolled { player_id, dice });
+                    Action::RollDice
+                },
+                CatanatronActionType::MoveRobber | CatanatronActionType::PlayKnightCard => {
+                    // A knight is played and then the robber moved, which is a single action here.
+                    let knight = action.1 == CatanatronActionType::PlayKnightCard;
+                    let (index, action) = if knight {
+                        match actions.next() {
+                            Some((index, next)) if next.1 == CatanatronActionType::MoveRobber && next.0 == action.0 => (index, next),
+                            _ => return Err(unsupported(index, String::from("The knight isn't followed by the player moving the robber."))),
+                        }
+                    } else {
+                        (index, action)
+                    };
+                    let (coordinate, victim, resource): ([i32; 3], Option<CatanatronColor>, Option<CatanatronResource>) = read(index, action)?;
+                    let tile = tile(index, coordinate)?;
+                    let victim = victim.map(|victim| player(index, victim)).transpose()?;
+                    if let (Some(victim), Some(resource)) = (victim, resource) {
+                        outcomes.push(Event::CardStolen { thief: player_id, victim, resource: resource.toResourceCard() });
+                    }
+                    if knight { Action::PlayKnight { tile, victim } } else { Action::MoveRobber { tile, victim } }
+                },
+                CatanatronActionType::BuildSettlement => Action::BuildSettlement { node: node(index, read(index, action)?)? },
+                CatanatronActionType::BuildCity => Action::BuildCity { node: node(index, read(index, action)?)? },
+                CatanatronActionType::BuildRoad => Action::BuildRoad { edge: edge(index, read(index, action)?)? },
+                CatanatronActionType::BuyDevelopmentCard => {
+                    let development_card: Option<CatanatronDevelopmentCard> = read(index, action)?;
+                    let development_card = development_card.ok_or(unsupported(index, String::from("The development card bought isn't given.")))?;
+                    outcomes.push(Event::DevelopmentCardDrawn { player_id, development_card: development_card.toDevelopmentCard() });
+                    Action::BuyDevelopmentCard
+                },
+                CatanatronActionType::PlayYearOfPlenty => {
+                    let resources: Vec<CatanatronResource> = read(index, action)?;
+                    let [first_resource, second_resource] = resources[..] else {
+                        return Err(unsupported(index, String::from("Year of plenty takes two cards here.")));
+                    };
+                    Action::PlayYearOfPlenty { first_resource: first_resource.toResourceCard(), second_resource: second_resource.toResourceCard() }
+                },
+                CatanatronActionType::PlayMonopoly => {
+                    let resource: CatanatronResource = read(index, action)?;
+                    Action::PlayMonopoly { resource: resource.toResourceCard() }
+                },
+                CatanatronActionType::PlayRoadBuilding => Action::PlayRoadBuilding,
+                CatanatronActionType::MaritimeTrade => {
+                    let cards: [Option<CatanatronResource>; 5] = read(index, action)?;
+                    let giving: Vec<CatanatronResource> = cards[..4].iter().flatten().copied().collect();
+                    let Some(receiving) = cards[4] else {
+                        return Err(unsupported(index, String::from("The maritime trade receives nothing.")));
+                    };
+                    Action::MaritimeTrade { giving: count_resources(&giving), receiving: count_resources(&[receiving]) }
+                },
+                CatanatronActionType::Discard => {
+                    let resources: Option<Vec<CatanatronResource>> = read(index, action)?;
+                    let resources = resources.ok_or(unsupported(index, String::from("The cards discarded aren't given.")))?;
+                    Action::Discard { resources: count_resources(&resources) }
+                },
+                CatanatronActionType::EndTurn => Action::EndTurn,
+                CatanatronActionType::OfferTrade | CatanatronActionType::AcceptTrade | CatanatronActionType::RejectTrade
+                    | CatanatronActionType::ConfirmTrade | CatanatronActionType::CancelTrade => {
+                    return Err(unsupported(index, String::from("Trades between players can't be read from a Catanatron game.")));
+                },
+            };
+            events.push(Event::ActionTaken { player_id, action: taken });
+            events.extend(outcomes);
+        }
+
+        Ok(GameRecord { version: RECORD_FORMAT_VERSION, board: board.toBoardString(), num_players, events })
+    }
+}
+
+#[allow(non_snake_case)]
+impl Game {
+    // Writes the game as Catanatron's game JSON, see CatanatronGame.
+    pub fn toCatanatronJson(&self) -> Result<String, String> {
+        Ok(serde_json::to_string(&CatanatronGame::fromGame(self)?).unwrap())
+    }
+
+    // Plays back a game from Catanatron's game JSON, checking it ends with the robber and winner it gives.
+    pub fn fromCatanatronJson(json: &str) -> Result<Game, ReplayError> {
+        let catanatron_game: CatanatronGame = serde_json::from_str(json)
+            .map_err(|error| ReplayError::InvalidLine { line: error.line(), message: error.to_string() })?;
+        let game = Game::fromRecord(&catanatron_game.toRecord()?)?;
+
+        let view = game.view(None);
+        if to_cube(HexCoord::fromIndex(view.board.robber, Edition::Base).unwrap()) != catanatron_game.robber_coordinate {
+            return Err(ReplayError::InvalidSetup(String::from("The game played back doesn't end with the robber where the game says.")));
+        }
+        if view.winner.map(|winner| catanatron_game.colors[winner]) != catanatron_game.winning_color {
+            return Err(ReplayError::InvalidSetup(String::from("The game played back doesn't end with the winner the game says.")));
+        }
+        Ok(game)
+    }
+}
@@ -0,0 +1,13 @@
+use serde::{Serialize, Deserialize};
+
+// How the number chits are placed on the land tiles when a board is created.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ChitLayout {
+    // Chits are shuffled onto the tiles with no restrictions.
+    Random,
+    // Chits are laid in alphabetical order in a spiral from a corner, as in the official rules.
+    Spiral,
+    // Chits are shuffled onto the tiles, but no 6 or 8 may be next to another 6 or 8.
+    #[default]
+    Balanced,
+}
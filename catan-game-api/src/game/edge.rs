@@ -1,31 +1,24 @@
-use std::sync::{Arc, Mutex};
 use serde::{Serialize, Deserialize};
 
-use crate::game::node::Node;
 use crate::game::building::Building;
 
-#[derive(Serialize, Deserialize)]
-pub struct Edge<'a> {
+// A side of a tile where roads are built. Adjacency lives in the board's tables.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Edge {
     pub position: usize,
     pub building: Option<Building>,
-    #[serde(skip)]
-    pub adjacent_nodes: Vec<Arc<Mutex<Node<'a>>>>
 }
 
 #[allow(non_snake_case)]
-impl <'b> Edge<'b> {
+impl Edge {
     pub fn new(position: usize) -> Self {
         Edge {
-            position: position,
+            position,
             building: None,
-            adjacent_nodes: vec![]
         }
     }
-    
+
     pub fn hasRoad(&self) -> bool{
-        match self.building {
-            Some(_) => true,
-            _ => false
-        }
+        self.building.is_some()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,28 @@
+use serde::{Serialize, Deserialize};
+
+// The fewest and most players a game can have.
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 6;
+
+// Which set of components a game is played with, which depends on the number of players.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum Edition {
+    // The standard board of 19 tiles, for 2 to 4 players.
+    #[default]
+    Base,
+    // The 5-6 player extension: a board of 30 tiles, more resource and development cards,
+    // and a special building phase between turns.
+    FiveSixPlayer,
+}
+
+#[allow(non_snake_case)]
+impl Edition {
+    // Returns the edition played with the given number of players, if it is a valid number of players.
+    pub fn forPlayers(num_players: usize) -> Option<Edition> {
+        match num_players {
+            MIN_PLAYERS..=4 => Some(Edition::Base),
+            5..=MAX_PLAYERS => Some(Edition::FiveSixPlayer),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Serialize, Deserialize};
+
+use crate::game::action::Action;
+use crate::game::development::DevelopmentCard;
+use crate::game::resource::ResourceCard;
+
+// Something that happened in a game. Each applied action is followed by the random outcomes it caused,
+// and anything it revealed.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub enum Event {
+    ActionTaken { player_id: usize, action: Action },
+    DiceRolled { player_id: usize, dice: [usize; 2] },
+    CardStolen { thief: usize, victim: usize, resource: ResourceCard },
+    DevelopmentCardDrawn { player_id: usize, development_card: DevelopmentCard },
+    // The winner's hidden victory point cards are shown when the game ends.
+    VictoryPointCardsRevealed { player_id: usize, amount: usize },
+    // The last action was taken back, or the last action taken back was applied again, see Game::undo.
+    ActionUndone,
+    ActionRedone,
+    // A trade offer wasn't settled in time and was withdrawn, see Game::expireTradeOffer.
+    TradeOfferExpired { offer_id: usize },
+}
@@ -1,50 +1,119 @@
 use serde::{Serialize, Deserialize};
-use std::borrow::BorrowMut;
 use std::collections::HashMap;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 
 use crate::game::player::Player;
 use crate::game::board::Board;
 use crate::game::bank::Bank;
 use crate::game::action::Action;
 use crate::game::building::Building;
-use crate::game::action::ActionType;
-use crate::game::trade_offer::TradeOffer;
+use crate::game::trade_offer::{TradeOffer, TradeResponse, TradeStatus};
 use crate::game::resource::ResourceCard;
-use crate::game::port::Port;
+use crate::game::chit_layout::ChitLayout;
+use crate::game::edition::{Edition, MIN_PLAYERS, MAX_PLAYERS};
+use crate::game::standing::Standing;
+use crate::game::action_result::{ActionResult, ActionError, StateDelta};
+use crate::game::view::{GameView, BankView, PlayerView};
+use crate::game::snapshot::GameSnapshot;
+use crate::game::history::{History, HistoryEntry, MAX_UNDO_ACTIONS};
+use crate::game::event::Event;
+use crate::game::replay::{EventLog, ReplayHeader, ReplayError, REPLAY_FORMAT_VERSION};
+use crate::game::record::{GameRecord, RECORD_FORMAT_VERSION};
 
 use super::development::DevelopmentCard;
+use super::phase::GamePhase;
 use super::terrain::Terrain;
 
+const VICTORY_POINTS_TO_WIN: usize = 10;
+
 #[derive(Serialize, Deserialize, Clone)]
-pub struct Game<'a> {
-    players: [Player; 4],
-    board: Board<'a>,
+pub struct Game {
+    players: Vec<Player>,
+    board: Board,
     bank: Bank,
     turn_number: i32,
     pub current_player_id: usize,
-    current_trade_offer: Option<TradeOffer>,
-    rolled_dice_this_turn: bool,
+    // Whose turn it is, while current_player_id is whoever has to act next, such as a player discarding after a seven.
+    turn_player_id: usize,
+    // The players who still have to discard half their hand after a seven, in turn order.
+    players_to_discard: Vec<usize>,
+    // What the game is waiting for next, which decides the actions that can be taken.
+    phase: GamePhase,
+    // Every trade offer made this turn, open or closed, in the order they were made. Several can be open at once.
+    trade_offers: Vec<TradeOffer>,
+    // The trade offers made in each earlier turn, from the first turn after setup.
+    past_trade_offers: Vec<Vec<TradeOffer>>,
+    // The id given to the next trade offer, so a response or timeout can't apply to a later offer.
+    next_trade_offer_id: usize,
     previous_dice_roll: usize,
-    players_accepted_trade_offer: [usize; 4],
-    game_ended: bool,
+    pub winner: Option<usize>,
+    // Players ordered from most to least victory points, set when the game ends.
+    pub final_standings: Vec<Standing>,
     last_turn_successful: bool,
-    last_placement_was_settlement: bool,
+    // The settlement just placed during setup, which the player's next road has to touch.
+    setup_settlement: Option<usize>,
+    // Only one development card, other than victory points, can be played each turn.
+    development_card_played: bool,
+    // In a 5-6 player game, the player whose turn just ended while the others get a chance to build.
+    special_building_after: Option<usize>,
+    // The seed the rng was created from, so the game can be played again exactly.
+    pub seed: u64,
+    chit_layout: ChitLayout,
+    // The board the game was set up on, if it wasn't made from the chit layout and seed.
+    board_string: Option<String>,
+    // Every applied action and the random outcomes it caused, see Game::eventLog.
+    events: Vec<Event>,
+    // The random outcomes the next action gives instead of drawing them, while a record is played back, see Game::fromRecord.
+    #[serde(skip)]
+    recorded_outcomes: Vec<Event>,
+    #[serde(skip, default = "rng_from_entropy")]
+    rng: StdRng,
+    #[serde(skip)]
+    history: History,
+}
+
+fn rng_from_entropy() -> StdRng {
+    StdRng::from_entropy()
 }
 
 #[allow(non_snake_case)]
-impl Game<'_> {
-    pub fn new() -> Self {
-        let mut players = core::array::from_fn(|index| {
-            Player::new(index)
-        });
-        
-        for i in 0..4 {
-            players[i] = Player::new(i);
+impl Game {
+    // Creates a new four player game. If no seed is given, a random one is chosen.
+    pub fn new(chit_layout: ChitLayout, seed: Option<u64>) -> Self {
+        Game::withPlayers(4, chit_layout, seed).unwrap()
+    }
+
+    // Creates a new game for 2 to 6 players. Games of 5 or 6 players are played with the 5-6 player extension.
+    pub fn withPlayers(num_players: usize, chit_layout: ChitLayout, seed: Option<u64>) -> Result<Self, String> {
+        let Some(edition) = Edition::forPlayers(num_players) else {
+            return Err(format!("A game needs {} to {} players.", MIN_PLAYERS, MAX_PLAYERS));
+        };
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // The board has its own rng, so a game set up on the same board from a board string plays out the same.
+        let board = Board::new(edition, chit_layout, &mut StdRng::seed_from_u64(rng.gen()));
+        Ok(Game::setup(num_players, board, chit_layout, None, seed, rng))
+    }
+
+    // Creates a new game on the given board, such as one read with Board::fromBoardString.
+    pub fn withBoard(board: Board, num_players: usize, seed: Option<u64>) -> Result<Self, String> {
+        if Edition::forPlayers(num_players) != Some(board.edition) {
+            return Err(format!("The board can't be played by {} players.", num_players));
         }
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // Skip the board's seed, to match a game created with Game::withPlayers.
+        rng.gen::<u64>();
+        let board_string = board.toBoardString();
+        Ok(Game::setup(num_players, board, ChitLayout::default(), Some(board_string), seed, rng))
+    }
 
-        let board = Board::new();
-        let bank = Bank::new();
+    fn setup(num_players: usize, board: Board, chit_layout: ChitLayout, board_string: Option<String>, seed: u64, mut rng: StdRng) -> Self {
+        let players = (0..num_players).map(Player::new).collect();
+        let bank = Bank::new(board.edition, &mut rng);
 
         Self {
             players,
@@ -52,807 +121,1142 @@ impl Game<'_> {
             bank,
             turn_number: 0,
             current_player_id: 0,
-            current_trade_offer: None,
-            rolled_dice_this_turn: false,
+            turn_player_id: 0,
+            players_to_discard: vec![],
+            phase: GamePhase::Setup,
+            trade_offers: vec![],
+            past_trade_offers: vec![],
+            next_trade_offer_id: 0,
             previous_dice_roll: 0,
-            players_accepted_trade_offer: [0, 0, 0, 0],
-            game_ended: false,
+            winner: None,
+            final_standings: vec![],
             last_turn_successful: false,
-            last_placement_was_settlement: false
+            setup_settlement: None,
+            development_card_played: false,
+            special_building_after: None,
+            seed,
+            chit_layout,
+            board_string,
+            events: vec![],
+            recorded_outcomes: vec![],
+            rng,
+            history: History::default()
         }
     }
 
-    pub fn reset(&mut self) {
-        let mut players = core::array::from_fn(|index| {
-            Player::new(index)
-        });
-        
-        for i in 0..4 {
-            players[i] = Player::new(i);
+    pub fn numPlayers(&self) -> usize {
+        self.players.len()
+    }
+
+    // Returns the ids of the open trade offers, oldest first.
+    pub fn openTradeOfferIds(&self) -> Vec<usize> {
+        self.trade_offers.iter().filter(|trade_offer| trade_offer.isOpen()).map(|trade_offer| trade_offer.id).collect()
+    }
+
+    // Returns the trade offers made this turn, including the ones that have been closed, and every response to them.
+    pub fn tradeOffers(&self) -> &[TradeOffer] {
+        &self.trade_offers
+    }
+
+    // Returns the trade offers made in each earlier turn, from the first turn after setup, with every response to them.
+    pub fn pastTradeOffers(&self) -> &[Vec<TradeOffer>] {
+        &self.past_trade_offers
+    }
+
+    fn openTradeOffer(&self, trade_offer_id: usize) -> Option<&TradeOffer> {
+        self.trade_offers.iter().find(|trade_offer| trade_offer.id == trade_offer_id && trade_offer.isOpen())
+    }
+
+    // Withdraws the trade offer with the given id on behalf of the player who made it, if it's still open.
+    // Used when an offer isn't settled in time. Returns whether the offer was withdrawn.
+    // Nobody took an action, so it can't be undone and doesn't drop the actions that can be redone.
+    pub fn expireTradeOffer(&mut self, trade_offer_id: usize) -> bool {
+        let Some(trade_offer) = self.openTradeOffer(trade_offer_id) else {
+            return false;
+        };
+        let player_offerer_id = trade_offer.player_offerer_id;
+        self.events.push(Event::TradeOfferExpired { offer_id: trade_offer_id });
+        self.applyAction(Action::DeclineTrade { offer_id: trade_offer_id }, player_offerer_id).is_ok()
+    }
+
+    pub fn phase(&self) -> GamePhase {
+        self.phase
+    }
+
+    pub fn isGameOver(&self) -> bool {
+        self.phase == GamePhase::GameOver
+    }
+
+    // Returns whether the game is in the special building phase of the 5-6 player extension.
+    pub fn isSpecialBuildingPhase(&self) -> bool {
+        self.phase == GamePhase::SpecialBuilding
+    }
+
+    // Returns whether the players are still placing their starting settlements and roads.
+    pub fn isSetupPhase(&self) -> bool {
+        self.phase == GamePhase::Setup
+    }
+
+    // Returns the player making the given settlement and road placement of setup, in snake order:
+    // forwards through the players for the first placements and backwards for the second.
+    fn setupPlayer(&self, placement: usize) -> usize {
+        let num_players = self.players.len();
+        if placement < num_players { placement } else { 2 * num_players - 1 - placement }
+    }
+
+    pub fn reset(&mut self, chit_layout: ChitLayout, seed: Option<u64>) {
+        *self = Game::new(chit_layout, seed);
+    }
+
+    // Takes a snapshot of the game, so a search can branch from this state and come back to it.
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot { game: self.clone() }
+    }
+
+    // Puts the game back into the state it was in when the snapshot was taken, including the dice to come.
+    // The undo history starts again from the restored state.
+    pub fn restore(&mut self, snapshot: &GameSnapshot) {
+        *self = snapshot.game.clone();
+    }
+
+    // Returns everything that has happened in the game so far, which Game::replay can play back.
+    pub fn eventLog(&self) -> EventLog {
+        EventLog {
+            header: ReplayHeader {
+                version: REPLAY_FORMAT_VERSION,
+                seed: self.seed,
+                num_players: self.players.len(),
+                chit_layout: self.chit_layout,
+                board: self.board_string.clone(),
+            },
+            events: self.events.clone(),
         }
+    }
 
-        let board = Board::new();
-        let bank = Bank::new();
-
-        self.players = players;
-        self.board = board;
-        self.bank = bank;
-        self.turn_number = 0;
-        self.current_player_id = 0;
-        self.current_trade_offer = None;
-        self.rolled_dice_this_turn = false;
-        self.previous_dice_roll = 0;
-        self.players_accepted_trade_offer = [0, 0, 0, 0];
-        self.game_ended = false;
-        self.last_turn_successful = false;
-        self.last_placement_was_settlement = false;
+    // Rebuilds a game by playing back the actions in a log, checking every random outcome matches the one recorded.
+    // Replaying a truncated log gives the game as it was at that point.
+    pub fn replay(log: &EventLog) -> Result<Game, ReplayError> {
+        if log.header.version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(log.header.version));
+        }
 
+        let header = &log.header;
+        let game = match &header.board {
+            Some(board_string) => {
+                let board = Board::fromBoardString(board_string).map_err(ReplayError::InvalidSetup)?;
+                Game::withBoard(board, header.num_players, Some(header.seed))
+            },
+            None => Game::withPlayers(header.num_players, header.chit_layout, Some(header.seed))
+        };
+        game.map_err(ReplayError::InvalidSetup)?.replayEvents(&log.events, false)
     }
 
-    // Takes an action on the game. Returns the next GameState and a boolean if the action was a success.
-    pub fn takeAction(&mut self, action: Action, player_id: usize) -> bool {
-        // If in the initial turns, then handle the action serparately.
-        if self.turn_number < 7 { 
-            self.turn_number += 1;
-            return self.handleInitialTurn(action, player_id);
+    // Writes the game as a record, with the board as a board string, see GameRecord.
+    pub fn toRecord(&self) -> GameRecord {
+        GameRecord {
+            version: RECORD_FORMAT_VERSION,
+            board: self.board_string.clone().unwrap_or_else(|| self.startingBoard().toBoardString()),
+            num_players: self.players.len(),
+            events: self.events.clone(),
         }
+    }
 
-        // Check and make sure the dice have been rolled this turn.
-        if self.rolled_dice_this_turn != true && action.action_type != ActionType::RollDice {
-            return false;
+    // Rebuilds a game from a record, see Game::replay. The dice, steals and development cards drawn are taken from
+    // the record instead of the rng, so the game plays out as recorded without knowing how it was seeded. The game gets a
+    // new seed, so it is played back from its record rather than its event log.
+    pub fn fromRecord(record: &GameRecord) -> Result<Game, ReplayError> {
+        if record.version != RECORD_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(record.version));
         }
+        let board = Board::fromBoardString(&record.board).map_err(ReplayError::InvalidSetup)?;
+        Game::withBoard(board, record.num_players, None).map_err(ReplayError::InvalidSetup)?.replayEvents(&record.events, true)
+    }
 
-        // Check and make sure that if their is a trade offer, the action type is accept or decline trade.
-        if self.current_trade_offer.is_some() && (action.action_type != ActionType::AcceptTrade || action.action_type != ActionType::DeclineTrade) {
-            return false;
+    // The board as it was before anything was built or the robber moved.
+    fn startingBoard(&self) -> Board {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        Board::new(self.board.edition, self.chit_layout, &mut StdRng::seed_from_u64(rng.gen()))
+    }
+
+    // Plays back the actions in the events on this game, checking every random outcome matches the one recorded.
+    // With use_recorded_outcomes, each action is given the outcomes recorded after it instead of drawing its own.
+    fn replayEvents(mut self, events: &[Event], use_recorded_outcomes: bool) -> Result<Game, ReplayError> {
+        let mut num_checked = 0;
+        for (index, event) in events.iter().enumerate() {
+            // Random outcomes are produced by the action before them, so only need to be compared.
+            match event {
+                Event::ActionTaken { player_id, action } => {
+                    if use_recorded_outcomes {
+                        self.recorded_outcomes = events[index + 1..].iter()
+                            .take_while(|event| matches!(event, Event::DiceRolled { .. } | Event::CardStolen { .. } | Event::DevelopmentCardDrawn { .. }))
+                            .cloned()
+                            .collect();
+                    }
+                    let result = self.takeAction(action.clone(), *player_id);
+                    self.recorded_outcomes.clear();
+                    result.map_err(|error| ReplayError::ActionRejected { index, error })?;
+                },
+                Event::ActionUndone => {
+                    self.undo();
+                },
+                Event::ActionRedone => {
+                    self.redo();
+                },
+                Event::TradeOfferExpired { offer_id } => {
+                    self.expireTradeOffer(*offer_id);
+                },
+                _ => {}
+            }
+
+            let num_events = self.events.len().min(events.len());
+            if let Some(mismatch) = (num_checked..num_events).find(|i| self.events[*i] != events[*i]) {
+                return Err(ReplayError::OutcomeMismatch { index: mismatch });
+            }
+            num_checked = num_events;
         }
+        Ok(self)
+    }
 
-        match action.action_type {
-            ActionType::AcceptTrade => {
-                // Check that there is a trade offer.
-                if !self.current_trade_offer.is_some() {
-                    return false;
-                }
+    // Takes back the last applied action. Returns false if there is nothing to undo.
+    // Dice and cards that were already seen aren't dealt the same way again, so undoing can't show what comes next.
+    pub fn undo(&mut self) -> bool {
+        let Some(previous) = self.history.undo_stack.pop_back() else { return false; };
+        let current = self.withoutEvents();
+        self.events.push(Event::ActionUndone);
+        self.restoreFromHistory(previous.game);
+        self.history.redo_stack.push(HistoryEntry { game: current, ..previous });
+        return true;
+    }
 
-                // Check that the player has the resources to accept the trade.
-                if !self.players[player_id].hasResourceCards(self.current_trade_offer.as_ref().unwrap().receiving_resources.clone()) {
-                    return false;
-                }
+    // Applies the last undone action again. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(next) = self.history.redo_stack.pop() else { return false; };
+        let current = self.withoutEvents();
+        self.events.push(Event::ActionRedone);
+        self.restoreFromHistory(next.game);
+        self.history.undo_stack.push_back(HistoryEntry { game: current, ..next });
+        return true;
+    }
 
-                // If the player is not the offering player, add them to the list of players who accepted the trade.
-                if self.current_trade_offer.as_ref().unwrap().player_offerer_id != player_id {
-                    self.current_player_id = (self.current_player_id + 1) % 4;
-                    self.players_accepted_trade_offer[player_id] = player_id;
-                    return true;
-                }
+    pub fn canUndo(&self) -> bool {
+        !self.history.undo_stack.is_empty()
+    }
 
-                // Otherwise, accept the trade from the player who offered the trade.
-                let player_to_accept_trade_from = action.action_metadata[0];
-                if self.players_accepted_trade_offer[player_to_accept_trade_from] == 0 {
-                    return false;
-                }
+    pub fn canRedo(&self) -> bool {
+        !self.history.redo_stack.is_empty()
+    }
 
-                // Choose which player to accept the trade from, and then trade the resources.
-                if !self.players[player_id].removeCardsFromHand(self.current_trade_offer.as_ref().unwrap().giving_resources.clone()) {
-                    return false;
-                }
+    // Returns whether the player can take back the last action, which they can only do if they took it
+    // and it didn't show anything hidden. Anyone in control of every seat can undo anything.
+    pub fn canPlayerUndo(&self, player_id: usize) -> bool {
+        self.history.undo_stack.back().is_some_and(|entry| entry.player_id == player_id && !entry.revealed_information)
+    }
+
+    pub fn canPlayerRedo(&self, player_id: usize) -> bool {
+        self.history.redo_stack.last().is_some_and(|entry| entry.player_id == player_id && !entry.revealed_information)
+    }
+
+    // Turns keeping the undo history on or off, clearing what was kept.
+    pub fn setHistoryEnabled(&mut self, enabled: bool) {
+        self.history = History { enabled, ..History::default() };
+    }
+
+    // Copies the game without its events, which the history doesn't need since they are only ever added to.
+    fn withoutEvents(&mut self) -> Game {
+        let events = std::mem::take(&mut self.events);
+        let game = self.clone();
+        self.events = events;
+        game
+    }
+
+    // Puts the game back into a state from its history. The events, the rng, the recorded outcomes and the offer ids carry on
+    // from where they are, and the development cards left are shuffled if any were drawn since, so nothing already seen is
+    // dealt the same way again.
+    fn restoreFromHistory(&mut self, mut game: Game) {
+        if game.bank.numDevelopmentCardsLeft() > self.bank.numDevelopmentCardsLeft() {
+            game.bank.shuffleUndrawnDevelopmentCards(&mut self.rng);
+        }
+        game.next_trade_offer_id = game.next_trade_offer_id.max(self.next_trade_offer_id);
+        std::mem::swap(&mut game.events, &mut self.events);
+        std::mem::swap(&mut game.rng, &mut self.rng);
+        std::mem::swap(&mut game.recorded_outcomes, &mut self.recorded_outcomes);
+        std::mem::swap(&mut game.history, &mut self.history);
+        *self = game;
+    }
+
+    // Takes an action on the game. Returns what changed in the game, or why the action was rejected.
+    pub fn takeAction(&mut self, action: Action, player_id: usize) -> ActionResult {
+        self.checkAction(&action, player_id)?;
+
+        let players_before = self.players.clone();
+        let is_dice_roll = action == Action::RollDice;
+        // Monopoly shows how many cards of the resource every other player had.
+        let is_monopoly = matches!(action, Action::PlayMonopoly { .. });
+
+        // Keep the state from before the action to undo it, which also rolls back an action that fails part way.
+        // Without a history nothing is copied, since checkAction has already made sure the action can be applied.
+        let before = if self.history.enabled { Some(self.withoutEvents()) } else { None };
+        let num_events = self.events.len();
+        self.events.push(Event::ActionTaken { player_id, action: action.clone() });
+        if let Err(action_error) = self.applyAction(action, player_id) {
+            let mut events = std::mem::take(&mut self.events);
+            events.truncate(num_events);
+            if let Some(before) = before {
+                let history = std::mem::take(&mut self.history);
+                *self = before;
+                self.history = history;
+            }
+            self.events = events;
+            return Err(action_error);
+        }
+
+        // Only the player whose turn it is can win, as soon as they reach the victory points needed. A player who
+        // reaches them on someone else's turn, such as in the special building phase, wins once their own turn starts.
+        if self.players[self.turn_player_id].totalVictoryPoints() >= VICTORY_POINTS_TO_WIN {
+            self.endGame(self.turn_player_id);
+        }
+
+        // Every event after the action itself is a random outcome or something revealed.
+        if let Some(before) = before {
+            let revealed_information = is_monopoly || self.events.len() > num_events + 1;
+            self.history.undo_stack.push_back(HistoryEntry { game: before, player_id, revealed_information });
+            if self.history.undo_stack.len() > MAX_UNDO_ACTIONS {
+                self.history.undo_stack.pop_front();
+            }
+            self.history.redo_stack.clear();
+        }
+
+        let dice_roll = if is_dice_roll { Some(self.previous_dice_roll) } else { None };
+        Ok(StateDelta::between(&players_before, &self.players, dice_roll, self.current_player_id))
+    }
+
+    // Takes several actions as one, such as road building and the roads it places from an older client, see
+    // ActionMessage::toActions. Either every action is taken or, if one is rejected, none are.
+    pub fn takeActions(&mut self, actions: Vec<Action>, player_id: usize) -> ActionResult {
+        if let [action] = &actions[..] {
+            return self.takeAction(action.clone(), player_id);
+        }
+
+        // Try the actions on a copy first. The copy has the same rng, so each action turns out the same way both times.
+        let mut trial = self.withoutEvents();
+        trial.setHistoryEnabled(false);
+        for action in actions.iter() {
+            trial.takeAction(action.clone(), player_id)?;
+        }
+
+        let players_before = self.players.clone();
+        let mut dice_roll = None;
+        for action in actions {
+            dice_roll = dice_roll.or(self.takeAction(action, player_id)?.dice_roll);
+        }
+        Ok(StateDelta::between(&players_before, &self.players, dice_roll, self.current_player_id))
+    }
+
+    // Returns the game as seen by whoever controls the given seat. Without a seat, the viewer controls
+    // every seat and sees every hand. The order of the development card deck is never shown.
+    pub fn view(&self, seat: Option<usize>) -> GameView<'_> {
+        GameView {
+            board: &self.board,
+            bank: BankView::new(&self.bank),
+            players: self.players.iter().map(|player| {
+                PlayerView::new(player, seat.is_none_or(|seat| seat == player.id), self.isGameOver())
+            }).collect(),
+            turn_number: self.turn_number,
+            current_player_id: self.current_player_id,
+            turn_player_id: self.turn_player_id,
+            players_to_discard: self.players_to_discard.clone(),
+            trade_offers: self.trade_offers.clone(),
+            phase: self.phase,
+            previous_dice_roll: self.previous_dice_roll,
+            game_ended: self.isGameOver(),
+            winner: self.winner,
+            final_standings: self.final_standings.clone(),
+        }
+    }
+
+    // Ends the game with the given player as the winner, and records every player's victory points.
+    fn endGame(&mut self, winner: usize) {
+        let mut final_standings: Vec<Standing> = self.players.iter().map(|player| {
+            Standing {
+                player_id: player.id,
+                victory_points: player.totalVictoryPoints()
+            }
+        }).collect();
+        final_standings.sort_by_key(|standing| std::cmp::Reverse(standing.victory_points));
+
+        // The winner's victory point cards are revealed, since they count towards the win.
+        let victory_point_cards = self.players[winner].victoryPointCards();
+        if victory_point_cards > 0 {
+            self.events.push(Event::VictoryPointCardsRevealed { player_id: winner, amount: victory_point_cards });
+        }
+
+        self.phase = GamePhase::GameOver;
+        self.winner = Some(winner);
+        self.final_standings = final_standings;
+    }
 
-                if !self.players[action.action_metadata[0]].removeCardsFromHand(self.current_trade_offer.as_ref().unwrap().receiving_resources.clone()) {
-                    self.players[player_id].addResourceCards(self.current_trade_offer.as_ref().unwrap().giving_resources.clone());
-                    return false;
+    // Checks whether the player can take an action, returns why not if they can't.
+    // Nothing in the game is changed, so this is also used to work out which actions are legal.
+    pub fn checkAction(&self, action: &Action, player_id: usize) -> Result<(), ActionError> {
+        // No actions can be taken once someone has won.
+        if self.phase == GamePhase::GameOver {
+            return Err(ActionError::GameOver);
+        }
+
+        // The other players respond to a trade offer out of turn.
+        let is_trade_response = matches!(action, Action::AcceptTrade { .. } | Action::DeclineTrade { .. } | Action::CounterTrade { .. });
+        let is_responding_to_offer = self.phase == GamePhase::TradeNegotiation && is_trade_response && player_id < self.players.len();
+        if player_id != self.current_player_id && !is_responding_to_offer {
+            return Err(ActionError::NotYourTurn);
+        }
+
+        self.checkPhase(action)?;
+
+        // If in the initial turns, then check the action serparately.
+        if self.phase == GamePhase::Setup {
+            return self.checkInitialTurn(action, player_id);
+        }
+
+        let player = &self.players[player_id];
+        match action {
+            Action::AcceptTrade { offer_id, player: accepting_player } => {
+                // Check that the trade offer is still open.
+                let Some(trade_offer) = self.openTradeOffer(*offer_id) else { return Err(ActionError::NoTradeOffer); };
+
+                // Check that the player has the resources to accept the trade.
+                if trade_offer.player_offerer_id != player_id {
+                    if !player.hasResourceCards(trade_offer.receiving_resources.clone()) {
+                        return Err(ActionError::InsufficientResources);
+                    }
+                    return Ok(());
                 }
 
-                // Trade was successful.
-                return true;
+                // The player who offered the trade has to choose a player who accepted or countered it.
+                let Some((offerer_giving, accepter_giving)) = trade_offer.termsWith(*accepting_player) else {
+                    return Err(ActionError::InvalidTrade);
+                };
+                if !player.hasResourceCards(offerer_giving) || !self.players[*accepting_player].hasResourceCards(accepter_giving) {
+                    return Err(ActionError::InsufficientResources);
+                }
+                return Ok(());
             },
-            ActionType::DeclineTrade => {
-                if !self.current_trade_offer.is_some() {
-                    return false;
+            Action::DeclineTrade { offer_id } => {
+                if self.openTradeOffer(*offer_id).is_none() {
+                    return Err(ActionError::NoTradeOffer);
                 }
-
-                self.current_player_id = (self.current_player_id + 1) % 4;
-                return true;
+                return Ok(());
             },
-            ActionType::Discard => {
-                let removed_cards = HashMap::from([
-                    (ResourceCard::Ore, action.action_metadata[0]),
-                    (ResourceCard::Wheat, action.action_metadata[1]),
-                    (ResourceCard::Sheep, action.action_metadata[2]),
-                    (ResourceCard::Brick, action.action_metadata[3]),
-                    (ResourceCard::Lumber, action.action_metadata[4]),
-                ]);
-                let mut current_player = self.players[self.current_player_id].clone();
+            Action::CounterTrade { offer_id, giving, receiving } => {
+                let Some(trade_offer) = self.openTradeOffer(*offer_id) else { return Err(ActionError::NoTradeOffer); };
 
-                // Check that the current player has the amount of resource cards to discard.
-                if !current_player.hasResourceCards(removed_cards.clone()) {
-                    return false;
+                // Only the other players can counter the offer, with a trade they could make.
+                if trade_offer.player_offerer_id == player_id {
+                    return Err(ActionError::InvalidTrade);
                 }
-
-                // Get the number of cards the player has in their hand.
-                let mut num_cards = 0;
-                for (_, value) in current_player.resource_cards.iter() {
-                    num_cards += value;
+                check_domestic_trade(giving, receiving)?;
+                if !player.hasResourceCards(giving.clone()) {
+                    return Err(ActionError::InsufficientResources);
                 }
-
-                // Get the number of cards attempting to discard.
-                let num_discarded_cards = 0;
-                for (_, value) in removed_cards.iter() {
-                    num_cards += *value;
+                return Ok(());
+            },
+            Action::Discard { resources } => {
+                // Check that the current player has the amount of resource cards to discard.
+                if !player.hasResourceCards(resources.clone()) {
+                    return Err(ActionError::InvalidDiscard);
                 }
 
-                // Checks that the previous roll was 7, the number of cards the player has in their hand is 8 or more,
+                // Checks that the number of cards the player has in their hand is 8 or more,
                 // and that the number of cards attempting to be discarded are exactly half of their hand size.
-                if self.previous_dice_roll != 7 || num_cards < 8 || num_cards / 2 != num_discarded_cards {
-                    return false;
+                let num_cards = player.numResourceCards();
+                let num_discarded_cards: usize = resources.values().sum();
+                if num_cards < 8 || num_cards / 2 != num_discarded_cards {
+                    return Err(ActionError::InvalidDiscard);
                 }
-
-                current_player.removeCardsFromHand(removed_cards);
-                return true;
+                return Ok(());
             },
-            ActionType::DrawDevelopmentCard => {
-                let mut current_player = self.players[self.current_player_id].clone();
-                let development_card_resources = HashMap::from([(ResourceCard::Ore, 1), (ResourceCard::Wheat, 1), (ResourceCard::Sheep, 1)]);
-                
+            Action::BuyDevelopmentCard => {
                 // Check the player has the resource cards available to get a development card.
-                if !current_player.hasResourceCards(development_card_resources.clone()) {
-                    return false;
+                if !player.hasResourceCards(development_card_resources()) {
+                    return Err(ActionError::InsufficientResources);
                 }
 
                 // Check that their are still development cards left to draw.
-                let drawn_development_card = self.bank.drawDevelopmentCard();
-                if drawn_development_card.is_none() {
-                    return false;
+                if self.bank.numDevelopmentCardsLeft() == 0 {
+                    return Err(ActionError::NoDevelopmentCardsLeft);
                 }
-
-                // Remove the resources for the card and add it to the players hand.
-                current_player.removeCardsFromHand(development_card_resources);
-                current_player.addDevelopmentCard(drawn_development_card.unwrap().clone());
-                self.bank.replaceResourceCard(ResourceCard::Ore, 1);
-                self.bank.replaceResourceCard(ResourceCard::Wheat, 1);
-                self.bank.replaceResourceCard(ResourceCard::Sheep, 1);
-                return true;
+                return Ok(());
             },
-            ActionType::EndTurn => {
-                self.players[player_id].moveDevelopmentCards();
-                self.current_player_id = (self.current_player_id + 1) % 4; 
-                self.rolled_dice_this_turn = false;
-                return true;
+            Action::EndTurn => {
+                return Ok(());
             },
-            ActionType::OfferTrade => {
-                if self.offerPortTrade(action.clone(), player_id.clone()) {
-                    return true;
-                }
-
-                // Otherwise, setup a trade for the other players.
-                let giving_resources = HashMap::from([
-                    (ResourceCard::Ore, action.action_metadata[0]),
-                    (ResourceCard::Wheat, action.action_metadata[1]),
-                    (ResourceCard::Sheep, action.action_metadata[2]),
-                    (ResourceCard::Brick, action.action_metadata[3]),
-                    (ResourceCard::Lumber, action.action_metadata[4])
-                ]);
-                let receiving_resources = HashMap::from([
-                    (ResourceCard::Ore, action.action_metadata[5]),
-                    (ResourceCard::Wheat, action.action_metadata[6]),
-                    (ResourceCard::Sheep, action.action_metadata[7]),
-                    (ResourceCard::Brick, action.action_metadata[8]),
-                    (ResourceCard::Lumber, action.action_metadata[9])
-                ]);
-
-                if !self.players[player_id].hasResourceCards(giving_resources.clone()) {
-                    return false;
+            Action::MaritimeTrade { giving, receiving } => {
+                return self.checkMaritimeTrade(giving, receiving, player_id);
+            },
+            Action::OfferTrade { giving, receiving } => {
+                check_domestic_trade(giving, receiving)?;
+                if !player.hasResourceCards(giving.clone()) {
+                    return Err(ActionError::InsufficientResources);
                 }
-
-                self.current_trade_offer = Some(TradeOffer{
-                    player_offerer_id: player_id,
-                    giving_resources,
-                    receiving_resources
-                });
-                self.current_player_id = (self.current_player_id + 1) % 4;
-                return true;
+                return Ok(());
             },
-            ActionType::PlaceRobber => {
-                if action.action_metadata[0] >= self.board.tiles.len() {
-                    return false;
+            Action::MoveRobber { tile, victim } => {
+                return self.checkRobberPlacement(*tile, *victim, player_id);
+            },
+            Action::BuildCity { node } => {
+                if *node >= self.board.nodes.len() {
+                    return Err(ActionError::InvalidPosition);
                 }
 
-                // Check if the robber is on the current tile.
-                if self.board.tiles[action.action_metadata[0]].lock().unwrap().has_robber {
-                    return false;
+                if player.num_unplaced_cities == 0 {
+                    return Err(ActionError::NoPiecesLeft);
                 }
 
-                let mut has_node_owned_by_robbed_player = false;
-                for node in self.board.tiles[action.action_metadata[0]].lock().unwrap().adjacent_nodes.clone() {
-                    let mut node_n = node.lock().unwrap();
-                    let node_building = node_n.building.as_mut();
-                    if node_building.is_some() {
-                        match node_building.unwrap() {
-                            Building::Settlement(_, player) => {
-                                has_node_owned_by_robbed_player |= *player == action.action_metadata[1];
-                            },
-                            Building::City(_, player) => {
-                                has_node_owned_by_robbed_player |= *player == action.action_metadata[1];
-                            },
-                            _ => { continue; }
-                        }
-                    }
+                if !player.hasResourceCards(city_resources()) {
+                    return Err(ActionError::InsufficientResources);
                 }
 
-                if !has_node_owned_by_robbed_player {
-                    return false;
-                }
+                return self.board.canPlaceCity(&Building::City(*node, player_id));
+            },
+            Action::PlayKnight { tile, victim } => {
+                self.checkDevelopmentCardPlay(DevelopmentCard::Knight, player_id)?;
+                return self.checkRobberPlacement(*tile, *victim, player_id);
+            },
+            Action::PlayMonopoly { .. } => {
+                return self.checkDevelopmentCardPlay(DevelopmentCard::Monopoly, player_id);
+            },
+            Action::PlayRoadBuilding => {
+                self.checkDevelopmentCardPlay(DevelopmentCard::RoadBuilding, player_id)?;
 
-                // Set the robber to true on the tile and steal a card from the given player.
-                self.board.tiles[action.action_metadata[0]].lock().unwrap().has_robber = true;
-                let stolen_resource = self.players[action.action_metadata[1]].stealCard();
-                if stolen_resource.is_none() {
-                    return true;
+                // There has to be somewhere to place at least one of the roads.
+                if player.num_unplaced_roads == 0 {
+                    return Err(ActionError::NoPiecesLeft);
                 }
-                self.players[player_id].addResourceCards(HashMap::from([(stolen_resource.unwrap(), 1)]));
-                return true;
-            },
-            ActionType::PlayCity => {
-                if action.action_metadata[0] >= self.board.nodes.len() {
-                    return false;
+                if !self.canPlaceAnyRoad(player_id) {
+                    return Err(ActionError::InvalidPosition);
                 }
+                return Ok(());
+            },
+            Action::PlayYearOfPlenty { first_resource, second_resource } => {
+                self.checkDevelopmentCardPlay(DevelopmentCard::YearOfPlenty, player_id)?;
 
-                if !(self.players[player_id].num_unplaced_cities > 0) {
-                    return false;
+                // The resources are drawn from the bank, which may run out.
+                let amount_needed = if first_resource == second_resource { 2 } else { 1 };
+                if self.bank.amountOfResource(*first_resource) < amount_needed || self.bank.amountOfResource(*second_resource) < amount_needed {
+                    return Err(ActionError::InsufficientBankResources);
+                }
+                return Ok(());
+            },
+            Action::BuildRoad { edge } => {
+                // Check the road placement makes sense.
+                if *edge >= self.board.edges.len() {
+                    return Err(ActionError::InvalidPosition);
                 }
 
-                let city_resources = HashMap::from([(ResourceCard::Ore, 3), (ResourceCard::Wheat, 2)]);
-                if !self.players[player_id].hasResourceCards(city_resources.clone()) {
-                    return false;
+                if player.num_unplaced_roads == 0 {
+                    return Err(ActionError::NoPiecesLeft);
                 }
 
-                if !self.board.placeCity(Building::City(action.action_metadata[0], self.current_player_id)) {
-                    return false;
+                // Check the player has the resources for a road, unless it's free from road building.
+                let is_free = matches!(self.phase, GamePhase::RoadBuildingPending { .. });
+                if !is_free && !player.hasResourceCards(road_resources()) {
+                    return Err(ActionError::InsufficientResources);
                 }
 
-                self.players[player_id].removeCardsFromHand(city_resources);
-                self.bank.replaceResourceCard(ResourceCard::Ore, 3);
-                self.bank.replaceResourceCard(ResourceCard::Wheat, 2);
-                self.players[player_id].num_unplaced_cities -= 1;
-                self.players[player_id].num_unplaced_settlements += 1;
-                self.players[player_id].victory_points += 1;
-                self.players[player_id].city_placements.push(action.action_metadata[0]);
-                return true;
+                return self.board.canPlaceRoad(&Building::Road(*edge, player_id));
             },
-            ActionType::PlayDevelopmentCard => {
-                if action.action_metadata[0] > 5 {
-                    return false;
+            Action::BuildSettlement { node } => {
+                // Check that the settlement placement makes sense.
+                if *node >= self.board.nodes.len() {
+                    return Err(ActionError::InvalidPosition);
                 }
 
-                let attempted_development_card = match action.action_metadata[0] {
-                    0 => DevelopmentCard::Knight,
-                    1 => DevelopmentCard::Monopoly,
-                    2 => DevelopmentCard::RoadBuilding,
-                    3 => DevelopmentCard::VictoryPoint,
-                    4 => DevelopmentCard::YearOfPlenty,
-                    _ => DevelopmentCard::VictoryPoint
-                };
-
-                if attempted_development_card == DevelopmentCard::VictoryPoint {
-                    return false;
+                if player.num_unplaced_settlements == 0 {
+                    return Err(ActionError::NoPiecesLeft);
                 }
 
-                if !self.players[player_id].hasDevelopmentCard(attempted_development_card.clone()) {
-                    return false;
+                // Check the player has the resources in hand to build a settlement.
+                if !player.hasResourceCards(settlement_resources()) {
+                    return Err(ActionError::InsufficientResources);
                 }
 
-                if !self.handleDevelopmentCard(action, player_id) {
-                    return false;
-                }
-                self.players[player_id].removeDevelopmentCard(attempted_development_card);
-                return true;
+                return self.board.canPlaceSettlement(&Building::Settlement(*node, player_id));
             },
-            ActionType::PlayRoad => {
-                // Check the road placement makes sense.
-                if action.action_metadata[0] >= self.board.edges.len() {
-                    return false;
-                }
+            Action::RollDice => {
+                return Ok(());
+            }
+        }
+    }
 
-                if !(self.players[player_id].num_unplaced_roads > 0) {
-                    return false;
-                }
+    // Checks the action can be taken in the current phase of the game.
+    fn checkPhase(&self, action: &Action) -> Result<(), ActionError> {
+        let is_allowed = match self.phase {
+            GamePhase::Setup => matches!(action, Action::BuildSettlement { .. } | Action::BuildRoad { .. }),
+            // A knight can be played before rolling, to move the robber off one of the player's tiles.
+            GamePhase::PreRoll => matches!(action, Action::RollDice | Action::PlayKnight { .. }),
+            GamePhase::MustDiscard => matches!(action, Action::Discard { .. }),
+            GamePhase::MoveRobber => matches!(action, Action::MoveRobber { .. }),
+            GamePhase::MainPhase => !matches!(action, Action::RollDice | Action::Discard { .. } | Action::MoveRobber { .. } | Action::AcceptTrade { .. } | Action::DeclineTrade { .. } | Action::CounterTrade { .. }),
+            // The player whose turn it is can make more offers while others are still open.
+            GamePhase::TradeNegotiation => matches!(action, Action::OfferTrade { .. } | Action::AcceptTrade { .. } | Action::DeclineTrade { .. } | Action::CounterTrade { .. }),
+            GamePhase::RoadBuildingPending { .. } => matches!(action, Action::BuildRoad { .. }),
+            // Players can only build or buy development cards before passing.
+            GamePhase::SpecialBuilding => matches!(action, Action::BuildRoad { .. } | Action::BuildSettlement { .. } | Action::BuildCity { .. } | Action::BuyDevelopmentCard | Action::EndTurn),
+            GamePhase::GameOver => false,
+        };
+        if is_allowed {
+            return Ok(());
+        }
 
-                // Check the player has the resources for a road.
-                let road_resources = HashMap::from([(ResourceCard::Lumber, 1), (ResourceCard::Brick, 1)]);
-                if !self.players[player_id].hasResourceCards(road_resources.clone()) {
-                    return false;
-                }
+        // Give a more specific reason where there is one.
+        let action_error = match (self.phase, action) {
+            (GamePhase::GameOver, _) => ActionError::GameOver,
+            (GamePhase::PreRoll, _) => ActionError::DiceNotRolled,
+            (GamePhase::MainPhase, Action::RollDice) => ActionError::DiceAlreadyRolled,
+            (GamePhase::MainPhase, Action::AcceptTrade { .. } | Action::DeclineTrade { .. } | Action::CounterTrade { .. }) => ActionError::NoTradeOffer,
+            (GamePhase::TradeNegotiation, _) => ActionError::TradeOfferPending,
+            _ => ActionError::WrongPhase,
+        };
+        return Err(action_error);
+    }
 
-                // If the road cannot be played return false.
-                let (placed_road, _) = self.board.placeRoad(Building::Road(action.action_metadata[0], self.current_player_id));
-                if !placed_road {
-                    return false;
-                }
+    // Checks the player can play the development card. Cards drawn this turn can't be played until the
+    // player's next turn, and only one card can be played each turn.
+    fn checkDevelopmentCardPlay(&self, development_card: DevelopmentCard, player_id: usize) -> Result<(), ActionError> {
+        if !self.players[player_id].hasDevelopmentCard(development_card) {
+            return Err(ActionError::DevelopmentCardUnavailable);
+        }
+        if self.development_card_played {
+            return Err(ActionError::DevelopmentCardAlreadyPlayed);
+        }
+        return Ok(());
+    }
 
-                self.players[player_id].removeCardsFromHand(road_resources);
-                self.bank.replaceResourceCard(ResourceCard::Lumber, 1);
-                self.bank.replaceResourceCard(ResourceCard::Brick, 1);
-                self.players[player_id].num_unplaced_roads -= 1;
-                self.players[player_id].road_placements.push(action.action_metadata[0]);
+    // Checks whether the player has a road left and anywhere to build it.
+    fn canPlaceAnyRoad(&self, player_id: usize) -> bool {
+        self.players[player_id].num_unplaced_roads > 0 &&
+            (0..self.board.edges.len()).any(|edge| self.board.canPlaceRoad(&Building::Road(edge, player_id)).is_ok())
+    }
 
-                // Check if the player has the longest road.
-                let mut road_sizes: [usize; 4] = [0; 4];
-                for player in &self.players {
-                    road_sizes[player.id] = self.checkLongestRoad(player.id);
+    // Checks the robber can be moved to the tile, and that the victim has a building on it to steal from.
+    // Without a victim, none of the other players can have a building on the tile.
+    fn checkRobberPlacement(&self, tile: usize, victim: Option<usize>, player_id: usize) -> Result<(), ActionError> {
+        if tile >= self.board.tiles.len() {
+            return Err(ActionError::InvalidPosition);
+        }
+
+        // The robber has to move to a different tile.
+        if tile == self.board.robber {
+            return Err(ActionError::InvalidRobberPlacement);
+        }
+
+        let has_node_owned_by = |victim: usize| self.board.tileNodes(tile).iter().any(|node| {
+            matches!(self.board.nodeBuilding(*node), Some(Building::Settlement(_, player) | Building::City(_, player)) if player == victim)
+        });
+
+        let is_valid_victim = match victim {
+            Some(victim) => victim != player_id && has_node_owned_by(victim),
+            None => (0..self.players.len()).all(|other| other == player_id || !has_node_owned_by(other)),
+        };
+        if !is_valid_victim {
+            return Err(ActionError::InvalidRobberPlacement);
+        }
+        return Ok(());
+    }
+
+    fn checkInitialTurn(&self, action: &Action, player_id: usize) -> Result<(), ActionError> {
+        match action {
+            Action::BuildSettlement { node } => {
+                if self.setup_settlement.is_some() {
+                    return Err(ActionError::WrongPhase);
                 }
+                self.board.canPlaceInitialSettlement(&Building::Settlement(*node, player_id))
+            },
+            Action::BuildRoad { edge } => {
+                let Some(settlement) = self.setup_settlement else { return Err(ActionError::WrongPhase); };
+                self.board.canPlaceInitialRoad(&Building::Road(*edge, player_id), settlement)
+            },
+            _ => Err(ActionError::WrongPhase)
+        }
+    }
+
+    // Returns every action the player can take right now, so agents can mask out invalid actions.
+    // Trades offered to the other players are left out, since any combination of cards can be offered.
+    pub fn legal_actions(&self, player_id: usize) -> Vec<Action> {
+        if player_id >= self.players.len() {
+            return vec![];
+        }
+
+        let mut candidate_actions = vec![
+            Action::RollDice,
+            Action::EndTurn,
+            Action::BuyDevelopmentCard,
+            Action::PlayRoadBuilding,
+        ];
 
-                if road_sizes[player_id] < 5 {
-                    return true;
+        for node in 0..self.board.nodes.len() {
+            candidate_actions.push(Action::BuildSettlement { node });
+            candidate_actions.push(Action::BuildCity { node });
+        }
+
+        for edge in 0..self.board.edges.len() {
+            candidate_actions.push(Action::BuildRoad { edge });
+        }
+
+        // Which player is accepted only matters to the player who made the offer.
+        for trade_offer in self.trade_offers.iter().filter(|trade_offer| trade_offer.isOpen()) {
+            let offer_id = trade_offer.id;
+            candidate_actions.push(Action::DeclineTrade { offer_id });
+            for player in 0..self.players.len() {
+                if trade_offer.player_offerer_id == player_id || player == player_id {
+                    candidate_actions.push(Action::AcceptTrade { offer_id, player });
                 }
+            }
+        }
 
-                for (player, road_size) in road_sizes.iter().enumerate() {
-                    if player == player_id {
-                        continue;
-                    }
+        for tile in 0..self.board.tiles.len() {
+            let victims = (0..self.players.len()).map(Some).chain([None]);
+            for victim in victims {
+                candidate_actions.push(Action::MoveRobber { tile, victim });
+                candidate_actions.push(Action::PlayKnight { tile, victim });
+            }
+        }
 
-                    if road_sizes[player_id] <= *road_size {
-                        return true;
-                    } 
+        let maritime_rates = self.maritimeRates(player_id);
+        for (index, resource) in ResourceCard::ALL.into_iter().enumerate() {
+            candidate_actions.push(Action::PlayMonopoly { resource });
+            for other_resource in ResourceCard::ALL.into_iter().skip(index) {
+                candidate_actions.push(Action::PlayYearOfPlenty { first_resource: resource, second_resource: other_resource });
+            }
 
-                    if self.players[player].longest_road && *road_size < road_sizes[player_id] {
-                        self.players[player].longest_road = false;
-                        self.players[player].victory_points -= 2;
-                        self.players[player_id].longest_road = true;
-                        self.players[player_id].victory_points += 2;
-                        return true;
-                    }
+            // Maritime trades of a single batch of one resource for one of another resource.
+            // Trades of several batches are left out, since they are the same as making the trades one at a time.
+            for receiving_resource in ResourceCard::ALL {
+                let giving = HashMap::from([(resource, maritime_rates[&resource])]);
+                let receiving = HashMap::from([(receiving_resource, 1)]);
+                candidate_actions.push(Action::MaritimeTrade { giving, receiving });
+            }
+        }
+
+        // Every way of discarding half of the player's hand, going through the resources in a fixed order
+        // so the legal actions are always in the same order.
+        let num_cards = self.players[player_id].numResourceCards();
+        if num_cards >= 8 {
+            let mut discards: Vec<HashMap<ResourceCard, usize>> = vec![HashMap::new()];
+            for resource in ResourceCard::ALL {
+                let amount_in_hand = self.players[player_id].resource_cards[&resource];
+                discards = discards.iter().flat_map(|discard| {
+                    (0..=amount_in_hand).map(move |amount| {
+                        let mut discard = discard.clone();
+                        discard.insert(resource, amount);
+                        discard
+                    })
+                }).collect();
+            }
+            for resources in discards.into_iter().filter(|discard| discard.values().sum::<usize>() == num_cards / 2) {
+                candidate_actions.push(Action::Discard { resources });
+            }
+        }
+
+        candidate_actions.into_iter().filter(|action| self.checkAction(action, player_id).is_ok()).collect()
+    }
+
+    // Applies an action that has already been checked, then moves the game on to its next phase.
+    fn applyAction(&mut self, action: Action, player_id: usize) -> Result<(), ActionError> {
+        // If in the initial turns, then handle the action serparately.
+        if self.phase == GamePhase::Setup {
+            self.turn_number += 1;
+            self.handleInitialTurn(action.clone(), player_id)?;
+        } else {
+            self.handleTurnAction(action.clone(), player_id)?;
+        }
+        self.phase = self.nextPhase(&action, player_id);
+        if self.phase == GamePhase::PreRoll {
+            self.turn_player_id = self.current_player_id;
+        }
+        return Ok(());
+    }
+
+    // Works out the phase after an action has been applied. Every change of phase, except for the game ending, happens here.
+    fn nextPhase(&self, action: &Action, player_id: usize) -> GamePhase {
+        match (self.phase, action) {
+            // Each player places a settlement and a road twice, so setup takes four actions per player.
+            (GamePhase::Setup, _) if (self.turn_number as usize) < 4 * self.players.len() => GamePhase::Setup,
+            (GamePhase::Setup, _) => GamePhase::PreRoll,
+            (_, Action::RollDice) if self.previous_dice_roll != 7 => GamePhase::MainPhase,
+            (_, Action::RollDice | Action::Discard { .. }) if !self.players_to_discard.is_empty() => GamePhase::MustDiscard,
+            (_, Action::RollDice | Action::Discard { .. }) => GamePhase::MoveRobber,
+            (_, Action::MoveRobber { .. }) => GamePhase::MainPhase,
+            (_, Action::OfferTrade { .. } | Action::AcceptTrade { .. } | Action::DeclineTrade { .. } | Action::CounterTrade { .. }) if self.trade_offers.iter().any(|trade_offer| trade_offer.isOpen()) => GamePhase::TradeNegotiation,
+            (_, Action::OfferTrade { .. } | Action::AcceptTrade { .. } | Action::DeclineTrade { .. } | Action::CounterTrade { .. }) => GamePhase::MainPhase,
+            (_, Action::PlayRoadBuilding) => self.roadBuildingPhase(player_id, 2),
+            (GamePhase::RoadBuildingPending { roads_left }, Action::BuildRoad { .. }) => self.roadBuildingPhase(player_id, roads_left - 1),
+            (_, Action::EndTurn) if self.special_building_after.is_some() => GamePhase::SpecialBuilding,
+            (_, Action::EndTurn) => GamePhase::PreRoll,
+            (phase, _) => phase,
+        }
+    }
+
+    // Returns the phase for placing the free roads left from road building, which ends early if no more roads can be placed.
+    fn roadBuildingPhase(&self, player_id: usize, roads_left: usize) -> GamePhase {
+        if roads_left == 0 || !self.canPlaceAnyRoad(player_id) {
+            return GamePhase::MainPhase;
+        }
+        return GamePhase::RoadBuildingPending { roads_left };
+    }
+
+    // Applies an action taken after setup.
+    fn handleTurnAction(&mut self, action: Action, player_id: usize) -> Result<(), ActionError> {
+        match action {
+            Action::AcceptTrade { offer_id, player: accepting_player } => {
+                let trade_offer = self.trade_offers.iter_mut().find(|trade_offer| trade_offer.id == offer_id).unwrap();
+                if trade_offer.player_offerer_id != player_id {
+                    trade_offer.responses[player_id] = TradeResponse::Accepted;
+                    return Ok(());
+                }
+
+                // The player who made the offer confirms the trade, and both players' cards are exchanged at once.
+                let (offerer_giving, accepter_giving) = trade_offer.termsWith(accepting_player).unwrap();
+                trade_offer.status = TradeStatus::Completed { player: accepting_player };
+                self.players[player_id].removeCardsFromHand(offerer_giving.clone());
+                self.players[accepting_player].removeCardsFromHand(accepter_giving.clone());
+                self.players[player_id].addResourceCards(accepter_giving);
+                self.players[accepting_player].addResourceCards(offerer_giving);
+                return Ok(());
+            },
+            Action::DeclineTrade { offer_id } => {
+                let trade_offer = self.trade_offers.iter_mut().find(|trade_offer| trade_offer.id == offer_id).unwrap();
+
+                // The player who offered the trade declining it withdraws the offer.
+                if trade_offer.player_offerer_id == player_id {
+                    trade_offer.status = TradeStatus::Withdrawn;
+                    return Ok(());
                 }
 
-                self.players[player_id].longest_road = true;
-                self.players[player_id].victory_points += 2;
-                return true;
+                // The offer is closed once every other player has declined it.
+                trade_offer.responses[player_id] = TradeResponse::Declined;
+                let offerer_id = trade_offer.player_offerer_id;
+                if trade_offer.responses.iter().enumerate().all(|(id, response)| id == offerer_id || *response == TradeResponse::Declined) {
+                    trade_offer.status = TradeStatus::Declined;
+                }
+                return Ok(());
             },
-            ActionType::PlaySettlement => {
-                // Check that the settlement placement makes sense.
-                if action.action_metadata[0] >= self.board.nodes.len() {
-                    return false;
+            Action::CounterTrade { offer_id, giving, receiving } => {
+                let trade_offer = self.trade_offers.iter_mut().find(|trade_offer| trade_offer.id == offer_id).unwrap();
+                trade_offer.responses[player_id] = TradeResponse::Countered { giving, receiving };
+                return Ok(());
+            },
+            Action::Discard { resources } => {
+                self.players[player_id].removeCardsFromHand(resources.clone());
+                for (resource, amount) in resources {
+                    self.bank.replaceResourceCard(resource, amount);
                 }
 
-                if !(self.players[player_id].num_unplaced_settlements > 0) {
-                    return false;
+                // Pass on to the next player who has to discard, and then back to the player who rolled to move the robber.
+                self.players_to_discard.remove(0);
+                self.current_player_id = self.players_to_discard.first().copied().unwrap_or(self.turn_player_id);
+                return Ok(());
+            },
+            Action::BuyDevelopmentCard => {
+                let drawn_development_card = match self.nextRecordedOutcome() {
+                    Some(Event::DevelopmentCardDrawn { development_card, .. }) => self.bank.drawNamedDevelopmentCard(development_card),
+                    _ => None
+                }.unwrap_or_else(|| *self.bank.drawDevelopmentCard().unwrap());
+                self.events.push(Event::DevelopmentCardDrawn { player_id, development_card: drawn_development_card });
+
+                // Remove the resources for the card and add it to the players hand.
+                self.players[player_id].removeCardsFromHand(development_card_resources());
+                self.players[player_id].addDevelopmentCard(drawn_development_card);
+                for (resource, amount) in development_card_resources() {
+                    self.bank.replaceResourceCard(resource, amount);
                 }
+                return Ok(());
+            },
+            Action::EndTurn => {
+                let next_player_id = (player_id + 1) % self.players.len();
+                match self.special_building_after {
+                    // The special building phase ends once every other player has had the chance to build.
+                    // Cards bought in it were bought before the next turn, so they can be played from then on.
+                    Some(turn_player_id) if next_player_id == turn_player_id => {
+                        self.special_building_after = None;
+                        self.current_player_id = (turn_player_id + 1) % self.players.len();
+                        self.players.iter_mut().for_each(|player| player.moveDevelopmentCards());
+                    },
+                    Some(_) => {
+                        self.current_player_id = next_player_id;
+                    },
+                    // Only a real turn ending resets the state kept for the turn, not a player passing in the special building phase.
+                    None => {
+                        self.players[player_id].moveDevelopmentCards();
+                        // Each turn starts with no trade offers, and the offers of the turn that ended are kept with the earlier turns'.
+                        let trade_offers = std::mem::take(&mut self.trade_offers);
+                        self.past_trade_offers.push(trade_offers);
+                        self.development_card_played = false;
+                        if self.board.edition == Edition::FiveSixPlayer {
+                            self.special_building_after = Some(player_id);
+                        }
+                        self.current_player_id = next_player_id;
+                    }
+                }
+                return Ok(());
+            },
+            Action::MaritimeTrade { giving, receiving } => {
+                self.makeMaritimeTrade(giving, receiving, player_id);
+                return Ok(());
+            },
+            Action::OfferTrade { giving, receiving } => {
+                // The trade is offered to every other player at once.
+                self.trade_offers.push(TradeOffer{
+                    id: self.next_trade_offer_id,
+                    player_offerer_id: player_id,
+                    giving_resources: giving,
+                    receiving_resources: receiving,
+                    responses: vec![TradeResponse::Pending; self.players.len()],
+                    status: TradeStatus::Open
+                });
+                self.next_trade_offer_id += 1;
+                return Ok(());
+            },
+            Action::MoveRobber { tile, victim } => {
+                self.moveRobber(tile, victim, player_id);
+                return Ok(());
+            },
+            Action::BuildCity { node } => {
+                self.board.placeCity(Building::City(node, player_id))?;
 
-                // Check the player has the resources in hand to build a settlement.
-                let settlement_resources = HashMap::from([
-                    (ResourceCard::Lumber, 1),
-                    (ResourceCard::Brick, 1),
-                    (ResourceCard::Wheat, 1),
-                    (ResourceCard::Sheep, 1)
-                ]);
-
-                if !self.players[player_id].hasResourceCards(settlement_resources.clone()) {
-                    return false;
+                self.players[player_id].removeCardsFromHand(city_resources());
+                for (resource, amount) in city_resources() {
+                    self.bank.replaceResourceCard(resource, amount);
+                }
+                self.players[player_id].num_unplaced_cities -= 1;
+                self.players[player_id].num_unplaced_settlements += 1;
+                self.players[player_id].victory_points += 1;
+                self.players[player_id].city_placements.push(node);
+                return Ok(());
+            },
+            Action::PlayKnight { tile, victim } => {
+                self.moveRobber(tile, victim, player_id);
+                self.players[player_id].removeDevelopmentCard(DevelopmentCard::Knight);
+                self.development_card_played = true;
+                self.players[player_id].num_knights_played += 1;
+                self.updateLargestArmy(player_id);
+                return Ok(());
+            },
+            Action::PlayMonopoly { resource } => {
+                let mut amount_to_add: usize = 0;
+                for player in self.players.iter_mut() {
+                    if player.id == player_id {
+                        continue;
+                    }
+                    amount_to_add += player.removeAllResourcesFromHand(resource);
                 }
 
-                if !self.board.placeSettlement(Building::Settlement(action.action_metadata[0], self.current_player_id)) {
-                    return false;
+                // Add the resource to the players hand.
+                self.players[player_id].addResourceCardAmount(resource, amount_to_add);
+                self.players[player_id].removeDevelopmentCard(DevelopmentCard::Monopoly);
+                self.development_card_played = true;
+                return Ok(());
+            },
+            Action::PlayRoadBuilding => {
+                self.players[player_id].removeDevelopmentCard(DevelopmentCard::RoadBuilding);
+                self.development_card_played = true;
+                return Ok(());
+            },
+            Action::PlayYearOfPlenty { first_resource, second_resource } => {
+                for resource in [first_resource, second_resource] {
+                    self.bank.drawNumberOfResourceCards(resource, 1);
+                    self.players[player_id].addResourceCard(resource);
+                }
+                self.players[player_id].removeDevelopmentCard(DevelopmentCard::YearOfPlenty);
+                self.development_card_played = true;
+                return Ok(());
+            },
+            Action::BuildRoad { edge } => {
+                self.board.placeRoad(Building::Road(edge, player_id))?;
+
+                // Roads from road building are free.
+                if !matches!(self.phase, GamePhase::RoadBuildingPending { .. }) {
+                    self.players[player_id].removeCardsFromHand(road_resources());
+                    for (resource, amount) in road_resources() {
+                        self.bank.replaceResourceCard(resource, amount);
+                    }
                 }
+                self.players[player_id].num_unplaced_roads -= 1;
+                self.players[player_id].road_placements.push(edge);
+                self.updateLongestRoad(player_id);
+                return Ok(());
+            },
+            Action::BuildSettlement { node } => {
+                self.board.placeSettlement(Building::Settlement(node, player_id))?;
 
-                self.players[player_id].removeCardsFromHand(settlement_resources);
-                self.bank.replaceResourceCard(ResourceCard::Lumber, 1);
-                self.bank.replaceResourceCard(ResourceCard::Wheat, 1);
-                self.bank.replaceResourceCard(ResourceCard::Brick, 1);
-                self.bank.replaceResourceCard(ResourceCard::Sheep, 1);
+                self.players[player_id].removeCardsFromHand(settlement_resources());
+                for (resource, amount) in settlement_resources() {
+                    self.bank.replaceResourceCard(resource, amount);
+                }
                 self.players[player_id].victory_points += 1;
                 self.players[player_id].num_unplaced_settlements -= 1;
-                self.players[player_id].settlement_placements.push(action.action_metadata[0]);
-                return true;
+                self.players[player_id].settlement_placements.push(node);
+                return Ok(());
             },
-            ActionType::RollDice => {
-                if self.rolled_dice_this_turn {
-                    return false;
-                }
-
+            Action::RollDice => {
                 // Roll the dice and produce on the relevant tiles.
-                let roll_1 = rand::thread_rng().gen_range(1..6);
-                let roll_2 = rand::thread_rng().gen_range(1..6);
+                let [roll_1, roll_2] = match self.nextRecordedOutcome() {
+                    Some(Event::DiceRolled { dice, .. }) if dice.iter().all(|die| (1..=6).contains(die)) => dice,
+                    _ => [self.rng.gen_range(1..=6), self.rng.gen_range(1..=6)]
+                };
+                self.events.push(Event::DiceRolled { player_id, dice: [roll_1, roll_2] });
 
                 self.previous_dice_roll = roll_1 + roll_2;
-                self.rolled_dice_this_turn = true;
                 if self.previous_dice_roll == 7 {
-                    return true;
+                    // Everyone with more than seven cards discards half of them, starting with the player who rolled.
+                    let num_players = self.players.len();
+                    self.players_to_discard = (0..num_players).map(|offset| (player_id + offset) % num_players)
+                        .filter(|player| self.players[*player].numResourceCards() > 7)
+                        .collect();
+                    self.current_player_id = self.players_to_discard.first().copied().unwrap_or(player_id);
+                    return Ok(());
                 }
                 self.produceDiceRoll(self.previous_dice_roll);
-                return true;
+                return Ok(());
             }
         }
     }
 
-    // Offers a port trade, returns whether it was successful or not.
-    fn offerPortTrade(&mut self, action: Action, player_id: usize) -> bool {
-        // Check if all the trade resources offered are less than 2; no port trade can occur, return false;
-        if action.action_metadata[0..5].iter().map(|num_trade| { *num_trade < 2 }).all(|less_2| less_2) {
-            return false;
+    // Checks a maritime trade, where each resource given is a whole number of batches at the player's rate
+    // for it, and one card is received from the bank for each batch.
+    fn checkMaritimeTrade(&self, giving: &HashMap<ResourceCard, usize>, receiving: &HashMap<ResourceCard, usize>, player_id: usize) -> Result<(), ActionError> {
+        let maritime_rates = self.maritimeRates(player_id);
+        let mut num_batches = 0;
+        for (resource, amount) in giving.iter().filter(|(_, amount)| **amount > 0) {
+            let rate = maritime_rates[resource];
+            if amount % rate != 0 || receiving.get(resource).is_some_and(|amount| *amount > 0) {
+                return Err(ActionError::InvalidTrade);
+            }
+            num_batches += amount / rate;
         }
 
-        // Check if the receiving resources add to greater than one; if so, return false.
-        if action.action_metadata[5..10].iter().fold(0, |acc, x| x + acc) > 1 {
-            return false;
+        if num_batches == 0 || num_batches != receiving.values().sum::<usize>() {
+            return Err(ActionError::InvalidTrade);
         }
 
-        // Check if offering more than 1 resource type; if so, return false.
-        if action.action_metadata[0..5].iter().filter(|resource| **resource > 0).collect::<Vec<&usize>>().len() > 1 {
-            return false;
+        if !self.players[player_id].hasResourceCards(giving.clone()) {
+            return Err(ActionError::InsufficientResources);
         }
 
-        // Check if one of the nodes the player has a settlement or city on is a port node.
-        let port_nodes = [0, 1, 3, 4, 10, 11, 15, 16, 26, 32, 33, 38, 42, 46, 47, 49, 51, 52];
-        let player_has_building_on_port: bool = self.players[player_id].settlement_placements.iter().filter(|placement| {
-            port_nodes.contains(placement)
-        }).collect::<Vec<&usize>>().len() > 0 || self.players[player_id].city_placements.iter().filter(|placement| {
-            port_nodes.contains(placement)
-        }).collect::<Vec<&usize>>().len() > 0;
-
-        if !player_has_building_on_port {
-            return false;
+        if receiving.iter().any(|(resource, amount)| self.bank.amountOfResource(*resource) < *amount) {
+            return Err(ActionError::InsufficientBankResources);
         }
+        return Ok(());
+    }
 
-        let cloned_player = self.players[player_id].clone();
-        let mut settlements_on_ports = cloned_player.settlement_placements.iter().filter(|placement| {
-            port_nodes.contains(placement)
-        }).collect::<Vec<&usize>>().clone();
-        let mut cities_on_ports = cloned_player.city_placements.iter().filter(|placement| {
-            port_nodes.contains(placement)
-        }).collect::<Vec<&usize>>().clone();
-        settlements_on_ports.append(&mut cities_on_ports);
-
-        // Helper variable in case resources get traded.
-        let giving_resources = HashMap::from([
-            (ResourceCard::Ore, action.action_metadata[0]),
-            (ResourceCard::Wheat, action.action_metadata[1]),
-            (ResourceCard::Sheep, action.action_metadata[2]),
-            (ResourceCard::Brick, action.action_metadata[3]),
-            (ResourceCard::Lumber, action.action_metadata[4])
-        ]);
-
-        // Check whether the trade is valid.
-        for (port_num, nodes) in self.board.port_node_mapping.clone().iter().enumerate() {
-            let node1 = nodes.lock().unwrap().0;
-            let node2 = nodes.lock().unwrap().1;
-
-            let has_building_on_this_port = settlements_on_ports.iter().filter(
-                |node| { ***node == node1 || ***node == node2 }
-            ).collect::<Vec<&&usize>>().len() > 0;
+    // Makes a maritime trade that has already been checked.
+    fn makeMaritimeTrade(&mut self, giving: HashMap<ResourceCard, usize>, receiving: HashMap<ResourceCard, usize>, player_id: usize) {
+        self.players[player_id].removeCardsFromHand(giving.clone());
+        for (resource, amount) in giving {
+            self.bank.replaceResourceCard(resource, amount);
+        }
+        for (resource, amount) in receiving {
+            self.bank.drawNumberOfResourceCards(resource, amount);
+            self.players[player_id].addResourceCardAmount(resource, amount);
+        }
+    }
 
+    // Returns how many of each resource the player gives the bank for one card. Without a port it's four,
+    // a 3:1 port makes it three for every resource, and a 2:1 port makes it two for its resource.
+    pub fn maritimeRates(&self, player_id: usize) -> HashMap<ResourceCard, usize> {
+        let mut maritime_rates: HashMap<ResourceCard, usize> = ResourceCard::ALL.into_iter().map(|resource| (resource, 4)).collect();
+        let player = &self.players[player_id];
+        for (port, (node1, node2)) in self.board.ports.iter().zip(self.board.port_node_mapping.iter()) {
+            let has_building_on_this_port = player.settlement_placements.iter().chain(player.city_placements.iter())
+                .any(|node| node == node1 || node == node2);
             if !has_building_on_this_port {
                 continue;
             }
 
-            // Do the trade if valid.
-            match *self.board.ports[port_num].lock().unwrap() {
-                Port::ThreeToOne => {
-                    let offering_3_trade = action.action_metadata[0..5].iter()
-                        .filter(|num_to_trade| **num_to_trade == 3).collect::<Vec<&usize>>().len() > 0;
-
-                    if !offering_3_trade {
-                        continue;
-                    }
-
-                    if action.action_metadata[5] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Ore, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Ore);
-                        return true;
-                    } else if action.action_metadata[6] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Wheat, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Wheat);
-                        return true;
-                    } else if action.action_metadata[7] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Sheep, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Sheep);
-                        return true;
-                    } else if action.action_metadata[8] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Brick, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Brick);
-                        return true;
-                    } else if action.action_metadata[9] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Lumber, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Lumber);
-                        return true;
-                    }
-                },
-                Port::Brick => {
-                    let offering_2_trade_brick = action.action_metadata[3] == 2;
+            let resources = match port.resource() {
+                Some(resource) => vec![(resource, 2)],
+                None => ResourceCard::ALL.into_iter().map(|resource| (resource, 3)).collect(),
+            };
+            for (resource, rate) in resources {
+                maritime_rates.entry(resource).and_modify(|best_rate| *best_rate = rate.min(*best_rate));
+            }
+        }
+        maritime_rates
+    }
 
-                    if !offering_2_trade_brick {
-                        continue;
-                    }
+    // Moves the robber to the tile and steals a card from the victim, if there is one.
+    fn moveRobber(&mut self, tile: usize, victim: Option<usize>, player_id: usize) {
+        self.board.robber = tile;
+        let Some(victim) = victim else { return; };
+        let recorded_resource = match self.nextRecordedOutcome() {
+            Some(Event::CardStolen { resource, .. }) if self.players[victim].removeCardsFromHand(HashMap::from([(resource, 1)])) => Some(resource),
+            _ => None
+        };
+        if let Some(stolen_resource) = recorded_resource.or_else(|| self.players[victim].stealCard(&mut self.rng)) {
+            self.events.push(Event::CardStolen { thief: player_id, victim, resource: stolen_resource });
+            self.players[player_id].addResourceCard(stolen_resource);
+        }
+    }
 
-                    if action.action_metadata[5] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Ore, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Ore);
-                        return true;
-                    } else if action.action_metadata[6] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Wheat, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Wheat);
-                        return true;
-                    } else if action.action_metadata[7] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Sheep, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Sheep);
-                        return true;
-                    } else if action.action_metadata[8] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Brick, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Brick);
-                        return true;
-                    } else if action.action_metadata[9] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Lumber, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Lumber);
-                        return true;
-                    }
-                },
-                Port::Lumber => {
-                    let offering_2_trade_lumber = action.action_metadata[4] == 2;
-                    
-                    if !offering_2_trade_lumber {
-                        continue;
-                    }
+    // Takes the next random outcome recorded for the action being played back. An outcome that can't happen, such as
+    // stealing a card the victim doesn't have, is drawn as usual instead, so the replay finds it doesn't match.
+    fn nextRecordedOutcome(&mut self) -> Option<Event> {
+        if self.recorded_outcomes.is_empty() {
+            return None;
+        }
+        return Some(self.recorded_outcomes.remove(0));
+    }
 
-                    if action.action_metadata[5] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Ore, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Ore);
-                        return true;
-                    } else if action.action_metadata[6] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Wheat, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Wheat);
-                        return true;
-                    } else if action.action_metadata[7] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Sheep, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Sheep);
-                        return true;
-                    } else if action.action_metadata[8] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Brick, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Brick);
-                        return true;
-                    } else if action.action_metadata[9] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Lumber, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Lumber);
-                        return true;
-                    }
-                },
-                Port::Ore => {
-                    let offering_2_trade_ore = action.action_metadata[0] == 2;
-                    
-                    if !offering_2_trade_ore {
-                        continue;
-                    }
+    // Gives the player longest road if their new road makes it longer than everyone else's.
+    fn updateLongestRoad(&mut self, player_id: usize) {
+        let mut road_sizes = vec![0; self.players.len()];
+        for player in &self.players {
+            road_sizes[player.id] = self.checkLongestRoad(player.id);
+        }
 
-                    if action.action_metadata[5] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Ore, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Ore);
-                        return true;
-                    } else if action.action_metadata[6] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Wheat, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Wheat);
-                        return true;
-                    } else if action.action_metadata[7] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Sheep, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Sheep);
-                        return true;
-                    } else if action.action_metadata[8] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Brick, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Brick);
-                        return true;
-                    } else if action.action_metadata[9] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Lumber, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Lumber);
-                        return true;
-                    }
-                },
-                Port::Sheep => {
-                    let offering_2_trade_sheep = action.action_metadata[2] == 2;
-                    
-                    if !offering_2_trade_sheep {
-                        continue;
-                    }
+        if road_sizes[player_id] < 5 {
+            return;
+        }
 
-                    if action.action_metadata[5] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Ore, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Ore);
-                        return true;
-                    } else if action.action_metadata[6] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Wheat, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Wheat);
-                        return true;
-                    } else if action.action_metadata[7] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Sheep, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Sheep);
-                        return true;
-                    } else if action.action_metadata[8] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Brick, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Brick);
-                        return true;
-                    } else if action.action_metadata[9] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Lumber, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Lumber);
-                        return true;
-                    }
+        for (player, road_size) in road_sizes.iter().enumerate() {
+            if player == player_id {
+                continue;
+            }
 
-                },
-                Port::Wheat => {
-                    let offering_2_trade_wheat = action.action_metadata[1] == 2;
-                    
-                    if !offering_2_trade_wheat {
-                        continue;
-                    }
+            if road_sizes[player_id] <= *road_size {
+                return;
+            }
 
-                    if action.action_metadata[5] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Ore, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Ore);
-                        return true;
-                    } else if action.action_metadata[6] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Wheat, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Wheat);
-                        return true;
-                    } else if action.action_metadata[7] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Sheep, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Sheep);
-                        return true;
-                    } else if action.action_metadata[8] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Brick, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Brick);
-                        return true;
-                    } else if action.action_metadata[9] > 0 {
-                        if !self.bank.drawNumberOfResourceCards(ResourceCard::Lumber, 1) {
-                            return false;
-                        }
-                        self.players[player_id].borrow_mut().removeCardsFromHand(giving_resources.clone());
-                        self.players[player_id].addResourceCard(ResourceCard::Lumber);
-                        return true;
-                    }
-                }
+            if self.players[player].longest_road && *road_size < road_sizes[player_id] {
+                self.players[player].longest_road = false;
+                self.players[player].victory_points -= 2;
+                self.players[player_id].longest_road = true;
+                self.players[player_id].victory_points += 2;
+                return;
             }
         }
 
-        false
+        if !self.players[player_id].longest_road {
+            self.players[player_id].longest_road = true;
+            self.players[player_id].victory_points += 2;
+        }
     }
 
     fn checkLongestRoad(&self, player_id: usize) -> usize {
         // Check if the player has longest road
         let mut road_sizes: Vec<usize> = vec![];
         self.players[player_id].road_placements.iter().for_each(|road_placement| {
-            let adjacent_roads = self.board.edges[*road_placement].lock().unwrap().adjacent_nodes.iter()
-                .map(|node| {
-                    node.lock().unwrap().adjacent_edges.clone()
-                })
-                .flatten()
-                .filter(|road| { road.lock().unwrap().position != *road_placement })
-                .filter(|road| {
-                    match road.lock().unwrap().building {
-                        Some(Building::Road(_, player)) => {
-                            if player != player_id {
-                                return false;
-                            }
-                            return true;
-                        },
-                        _ => false
-                    }
-                })
-                .map(|road| {
-                    road.lock().unwrap().position
-                })
-                .collect();
-
+            let adjacent_roads = self.adjacentRoads(player_id, *road_placement, &[*road_placement]);
             road_sizes.push(self.checkRoadSize(player_id, 1, vec![*road_placement], adjacent_roads));
         });
 
-        return *road_sizes.iter().max().unwrap();
+        return road_sizes.iter().max().copied().unwrap_or(0);
     }
 
     fn checkRoadSize(&self, player_id: usize, road_length: usize, mut already_checked_roads: Vec<usize>, unchecked_roads: Vec<usize>) -> usize {
         let mut road_sizes: Vec<usize> = vec![];
         for road_placement in unchecked_roads {
             already_checked_roads.push(road_placement);
-            let adjacent_roads: Vec<usize> = self.board.edges[road_placement].lock().unwrap().adjacent_nodes.iter()
-                .map(|node| {
-                    node.lock().unwrap().adjacent_edges.clone()
-                })
-                .flatten()
-                .filter(|road| { !already_checked_roads.contains(&road.lock().unwrap().position) })
-                .filter(|road| {
-                    match road.lock().unwrap().building {
-                        Some(Building::Road(_, player)) => {
-                            if player != player_id {
-                                return false;
-                            }
-                            return true;
-                        }
-                        _ => false
-                    }
-                })
-                .map(|road| {
-                    road.lock().unwrap().position
-                })
-                .collect();
+            let adjacent_roads = self.adjacentRoads(player_id, road_placement, &already_checked_roads);
             road_sizes.push(self.checkRoadSize(player_id, road_length + 1, already_checked_roads.clone(), adjacent_roads));
         }
 
@@ -864,8 +1268,18 @@ impl Game<'_> {
         }
     }
 
+    // Returns the player's roads touching the edge, leaving out the roads already checked.
+    fn adjacentRoads(&self, player_id: usize, edge: usize, already_checked_roads: &[usize]) -> Vec<usize> {
+        self.board.edgeNodes(edge).iter()
+            .flat_map(|node| self.board.nodeEdges(*node).iter().copied())
+            .filter(|road| !already_checked_roads.contains(road))
+            .filter(|road| matches!(self.board.edgeBuilding(*road), Some(Building::Road(_, player)) if player == player_id))
+            .collect()
+    }
+
     fn produceDiceRoll(&mut self, dice_roll: usize) {
-        let producing_tiles = self.board.tiles.iter().filter(|tile| tile.lock().unwrap().chit == dice_roll as i32);
+        // The tile with the robber doesn't produce.
+        let producing_tiles = self.board.tiles.iter().filter(|tile| tile.chit == dice_roll as i32 && tile.index != self.board.robber);
 
         // <PlayerID, resources to draw>
         let mut resources_to_produce: HashMap<ResourceCard, usize> = HashMap::from([
@@ -876,52 +1290,49 @@ impl Game<'_> {
             (ResourceCard::Lumber, 0),
         ]);
 
-        let mut player_resource_production: HashMap<usize, HashMap<ResourceCard, usize>> = HashMap::from([
-            (0, resources_to_produce.clone()),
-            (1, resources_to_produce.clone()),
-            (2, resources_to_produce.clone()),
-            (3, resources_to_produce.clone())
-        ]);
+        let mut player_resource_production: HashMap<usize, HashMap<ResourceCard, usize>> = (0..self.players.len())
+            .map(|player_id| (player_id, resources_to_produce.clone()))
+            .collect();
 
         for tile in producing_tiles {
-            let cur_tile = tile.lock().unwrap();
-            let producing_nodes = cur_tile.adjacent_nodes.iter().filter(|node| {
-                node.lock().unwrap().hasBuilding()
+            let cur_tile = tile;
+            let producing_buildings = self.board.tileNodes(cur_tile.index).into_iter().filter_map(|node| {
+                self.board.nodeBuilding(node)
             });
 
             // todo!("Refactor this to draw cards from the bank.");
 
-            for node in producing_nodes {
-                match node.lock().unwrap().building.as_ref().unwrap() {
+            for building in producing_buildings {
+                match &building {
                     Building::City(_, player_id) => {
                         match cur_tile.terrain {
                             Terrain::Fields => {
                                 let resource_to_produce = ResourceCard::Wheat;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 2);
                             },
                             Terrain::Forest => {
                                 let resource_to_produce = ResourceCard::Lumber;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 2);
                             },
                             Terrain::Hills => {
                                 let resource_to_produce = ResourceCard::Brick;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 2);
                             },
                             Terrain::Plains => {
                                 let resource_to_produce = ResourceCard::Sheep;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 2);
                             },
                             Terrain::Mountains => {
                                 let resource_to_produce = ResourceCard::Ore;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 2;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 2);
                             },
@@ -932,31 +1343,31 @@ impl Game<'_> {
                         match cur_tile.terrain {
                             Terrain::Fields => {
                                 let resource_to_produce = ResourceCard::Wheat;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 1);
                             },
                             Terrain::Forest => {
                                 let resource_to_produce = ResourceCard::Lumber;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 1);
                             },
                             Terrain::Hills => {
                                 let resource_to_produce = ResourceCard::Brick;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 1);
                             },
                             Terrain::Plains => {
                                 let resource_to_produce = ResourceCard::Sheep;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 1);
                             },
                             Terrain::Mountains => {
                                 let resource_to_produce = ResourceCard::Ore;
-                                let new_val = player_resource_production.get(&player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
+                                let new_val = player_resource_production.get(player_id).unwrap().get(&resource_to_produce).unwrap() + 1;
                                 player_resource_production.get_mut(player_id).unwrap().insert(resource_to_produce, new_val);
                                 resources_to_produce.insert(resource_to_produce, *resources_to_produce.get(&resource_to_produce).unwrap() + 1);
                             },
@@ -970,11 +1381,11 @@ impl Game<'_> {
 
         // Check if the bank has enough to produce the resources.
         let mut can_produce: HashMap<ResourceCard, bool> = HashMap::from([
-            (ResourceCard::Ore, false),
-            (ResourceCard::Wheat, false),
-            (ResourceCard::Sheep, false),
-            (ResourceCard::Brick, false),
-            (ResourceCard::Lumber, false),
+            (ResourceCard::Ore, true),
+            (ResourceCard::Wheat, true),
+            (ResourceCard::Sheep, true),
+            (ResourceCard::Brick, true),
+            (ResourceCard::Lumber, true),
         ]);
 
         // Check which resources are produceable or not.
@@ -983,7 +1394,7 @@ impl Game<'_> {
 
                 // Check if only one player is attempting to produce that resource.
                 let mut num_players_trying_to_produce_resource = 0;
-                for (_, resources) in &player_resource_production {
+                for resources in player_resource_production.values() {
                     if *resources.get(&resource).unwrap() > 0 {
                         num_players_trying_to_produce_resource += 1;
                     }
@@ -1013,194 +1424,89 @@ impl Game<'_> {
         }
     }
 
-    fn handleDevelopmentCard(&mut self, action: Action, player_id: usize) -> bool {
-        match action.action_metadata[0] {
-            // Robber development card.
-            0 => {
-                if action.action_metadata[1] >= self.board.tiles.len() {
-                    return false;
-                }
-
-                // Check if the robber is on the current tile.
-                if self.board.tiles[action.action_metadata[1]].lock().unwrap().has_robber {
-                    return false;
-                }
-
-                let mut has_node_owned_by_robbed_player = false;
-                for node in self.board.tiles[action.action_metadata[1]].lock().unwrap().adjacent_nodes.clone() {
-                    match node.lock().unwrap().building.as_mut().unwrap() {
-                        Building::Settlement(_, player) => {
-                            has_node_owned_by_robbed_player |= *player == action.action_metadata[2];
-                        },
-                        Building::City(_, player) => {
-                            has_node_owned_by_robbed_player |= *player == action.action_metadata[2];
-                        },
-                        _ => { continue; }
-                    }
-                }
-
-                if !has_node_owned_by_robbed_player {
-                    return false;
-                }
-
-                // Set the robber to true on the tile and steal a card from the given player.
-                self.board.tiles[action.action_metadata[0]].lock().unwrap().has_robber = true;
-                let stolen_resource = self.players[action.action_metadata[1]].stealCard();
-
-                if !stolen_resource.is_none() {
-                    self.players[player_id].addResourceCards(HashMap::from([(stolen_resource.unwrap(), 1)]));
-                }
+    // Gives the player largest army if they have played more knights than everyone else.
+    fn updateLargestArmy(&mut self, player_id: usize) {
+        if self.players[player_id].num_knights_played < 3 || self.players[player_id].largest_army {
+            return;
+        }
 
-                self.players[player_id].num_knights_played += 1;
+        for player in &self.players {
+            if player.id == player_id { continue; }
+            if self.players[player_id].num_knights_played <= player.num_knights_played {
+                return;
+            }
+        }
 
-                // Check if the player has largest army.
-                if !self.players[player_id].num_knights_played >= 3 {
-                    return true;
-                }
+        for player in self.players.iter_mut() {
+            if player.largest_army {
+                player.largest_army = false;
+                player.victory_points -= 2;
+            }
+        }
 
-                for player in &self.players[0..4] {
-                    if player.id == player_id { continue; }
-                    if !(self.players[player_id].num_knights_played > player.num_knights_played){
-                        return true;
-                    }
-                }
+        self.players[player_id].largest_army = true;
+        self.players[player_id].victory_points += 2;
+    }
 
-                for player in self.players[0..4].as_mut() {
-                    if player.largest_army {
-                        player.largest_army = false;
-                        player.victory_points -= 2;
+    fn handleInitialTurn(&mut self, action: Action, player_id: usize) -> Result<(), ActionError> {
+        match action {
+            Action::BuildSettlement { node } => {
+                self.board.placeInitialSettlement(Building::Settlement(node, player_id))?;
+                self.players[player_id].num_unplaced_settlements -= 1;
+                self.players[player_id].victory_points += 1;
+                self.players[player_id].settlement_placements.push(node);
+                self.setup_settlement = Some(node);
+
+                // The second settlement collects one of each resource around it.
+                if self.players[player_id].settlement_placements.len() == 2 {
+                    for tile in self.board.nodeTiles(node) {
+                        if let Some(resource) = self.board.tiles[*tile].terrain.resource() {
+                            if self.bank.drawNumberOfResourceCards(resource, 1) {
+                                self.players[player_id].addResourceCard(resource);
+                            }
+                        }
                     }
                 }
-
-                self.players[player_id].largest_army = true;
-                self.players[player_id].victory_points += 2;
-                return true;
+                return Ok(());
             },
-            1 => {
-                // Make sure that the resource makes sense
-                if action.action_metadata[1] >= 5 {
-                    return false;
-                }
-                let mut amount_to_add: usize = 0;
-                for mut player in self.players.clone() {
-                    if player.id == player_id {
-                        continue;
-                    }
-                    match action.action_metadata[1] {
-                        0 => { amount_to_add += player.removeAllResourcesFromHand(ResourceCard::Ore); },
-                        1 => { amount_to_add += player.removeAllResourcesFromHand(ResourceCard::Wheat); },
-                        2 => { amount_to_add += player.removeAllResourcesFromHand(ResourceCard::Sheep); },
-                        3 => { amount_to_add += player.removeAllResourcesFromHand(ResourceCard::Brick); },
-                        4 => { amount_to_add += player.removeAllResourcesFromHand(ResourceCard::Lumber); },
-                        _ => { return false; }
-                    }
-                }
-
-                // Add the resource to the players hand.
-                match action.action_metadata[1] {
-                    0 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Ore, amount_to_add)])); },
-                    1 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Wheat, amount_to_add)])); },
-                    2 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Sheep, amount_to_add)])); },
-                    3 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Brick, amount_to_add)])); },
-                    4 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Lumber, amount_to_add)])); },
-                    _ => { return false; }
-                }
-                
+            Action::BuildRoad { edge } => {
+                self.board.placeInitialRoad(Building::Road(edge, player_id), self.setup_settlement.unwrap())?;
+                self.players[player_id].num_unplaced_roads -= 1;
+                self.players[player_id].road_placements.push(edge);
+                self.setup_settlement = None;
 
-                return true
+                // Once setup is over, the first player takes the first turn, who also placed last.
+                let placements_made = self.turn_number as usize / 2;
+                self.current_player_id = self.setupPlayer(placements_made.min(2 * self.players.len() - 1));
+                return Ok(());
             },
-            2 => {
-                if action.action_metadata[1] >= self.board.edges.len() || action.action_metadata[2] >= self.board.edges.len() {
-                    return false;
-                }
-
-                let road_1 = Building::Road(action.action_metadata[1], player_id);
-                let road_2 = Building::Road(action.action_metadata[2], player_id);
+            _ => Err(ActionError::WrongPhase)
+        }
+    }
+}
 
-                let (placed_road1, already_road1) = self.board.placeRoad(road_1.clone());
-                let (placed_road2, already_road2) = self.board.placeRoad(road_2.clone());
+// Checks a trade between players gives and receives something, and doesn't trade a resource for itself.
+fn check_domestic_trade(giving: &HashMap<ResourceCard, usize>, receiving: &HashMap<ResourceCard, usize>) -> Result<(), ActionError> {
+    let giving: Vec<ResourceCard> = giving.iter().filter(|(_, amount)| **amount > 0).map(|(resource, _)| *resource).collect();
+    let receiving: Vec<ResourceCard> = receiving.iter().filter(|(_, amount)| **amount > 0).map(|(resource, _)| *resource).collect();
+    if giving.is_empty() || receiving.is_empty() || giving.iter().any(|resource| receiving.contains(resource)) {
+        return Err(ActionError::InvalidTrade);
+    }
+    return Ok(());
+}
 
-                if !placed_road1 || !placed_road2 {
-                    if !already_road1 {
-                        self.board.removeRoad(road_1);
-                    }
-                    if !already_road2 {
-                        self.board.removeRoad(road_2);
-                    }
-                    return false;
-                }
+fn road_resources() -> HashMap<ResourceCard, usize> {
+    HashMap::from([(ResourceCard::Lumber, 1), (ResourceCard::Brick, 1)])
+}
 
-                return true;
-            },
-            3 => {
-                return false;
-            },
-            4 => {
-                if action.action_metadata[1] >= 5 || action.action_metadata[2] >= 5 {
-                    return false;
-                }
-                match action.action_metadata[1] {
-                    0 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Ore, 1)]));},
-                    1 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Wheat, 1)]));},
-                    2 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Sheep, 1)]));},
-                    3 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Brick, 1)]));},
-                    4 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Lumber, 1)]));},
-                    _ => {return false; }
-                }
-                match action.action_metadata[2] {
-                    0 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Ore, 1)]));},
-                    1 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Wheat, 1)]));},
-                    2 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Sheep, 1)]));},
-                    3 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Brick, 1)]));},
-                    4 => { self.players[player_id].addResourceCards(HashMap::from([(ResourceCard::Lumber, 1)]));},
-                    _ => {return false; }
-                }
+fn settlement_resources() -> HashMap<ResourceCard, usize> {
+    HashMap::from([(ResourceCard::Lumber, 1), (ResourceCard::Brick, 1), (ResourceCard::Wheat, 1), (ResourceCard::Sheep, 1)])
+}
 
-                return true;
-            }
-            _ => { return false; }
-        }
-    }
+fn city_resources() -> HashMap<ResourceCard, usize> {
+    HashMap::from([(ResourceCard::Ore, 3), (ResourceCard::Wheat, 2)])
+}
 
-    fn handleInitialTurn(&mut self, action: Action, player_id: usize) -> bool {
-        match action.action_type {
-            ActionType::PlaySettlement => {
-                if self.last_placement_was_settlement {
-                    return false;
-                }
-                let new_settlement = Building::Settlement(
-                    action.action_metadata[0],
-                    player_id
-                );
-                if self.board.placeInitialSettlement(new_settlement) {
-                    self.players[player_id].num_unplaced_settlements -= 1;
-                    self.players[player_id].victory_points += 1;
-                    self.players[player_id].settlement_placements.push(action.action_metadata[0]);
-                    self.last_placement_was_settlement = true;
-                    return true;
-                } else {
-                    return false;
-                }
-            },
-            ActionType::PlayRoad => {
-                if !self.last_placement_was_settlement {
-                    return false;
-                }
-                let new_road = Building::Settlement(
-                    action.action_metadata[0],
-                    player_id
-                );
-                if self.board.placeInitialRoad(new_road) {
-                    self.players[player_id].num_unplaced_roads -= 1;
-                    self.players[player_id].road_placements.push(action.action_metadata[0]);
-                    self.current_player_id = (self.current_player_id + 1) % 4;
-                    self.last_placement_was_settlement = true;
-                    return true;
-                } else { 
-                    return false;
-                }
-            },
-            _ => false
-        }
-    }
-}
\ No newline at end of file
+fn development_card_resources() -> HashMap<ResourceCard, usize> {
+    HashMap::from([(ResourceCard::Ore, 1), (ResourceCard::Wheat, 1), (ResourceCard::Sheep, 1)])
+}
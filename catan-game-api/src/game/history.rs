@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+use crate::game::game::Game;
+
+// The number of actions that can be undone. Older actions are forgotten, so a long game doesn't keep every state.
+pub const MAX_UNDO_ACTIONS: usize = 100;
+
+// The states of a game before each applied action, so actions can be undone and redone.
+pub struct History {
+    pub(crate) undo_stack: VecDeque<HistoryEntry>,
+    pub(crate) redo_stack: Vec<HistoryEntry>,
+    // Whether states are kept at all. Games nobody undoes, such as simulations, can turn it off to save memory.
+    pub(crate) enabled: bool,
+}
+
+// The state of a game on one side of an action, without the game's events, which are only ever added to.
+pub(crate) struct HistoryEntry {
+    pub(crate) game: Game,
+    // The player who took the action.
+    pub(crate) player_id: usize,
+    // Whether the action showed anything that was hidden, such as the dice, a drawn card or another player's hand.
+    pub(crate) revealed_information: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            enabled: true,
+        }
+    }
+}
+
+// A copy of a game starts its own history, so branching a search from a game doesn't copy every state before it.
+impl Clone for History {
+    fn clone(&self) -> Self {
+        History {
+            enabled: self.enabled,
+            ..History::default()
+        }
+    }
+}
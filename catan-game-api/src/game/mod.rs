@@ -1,14 +1,27 @@
 pub mod action;
+pub mod action_result;
 pub mod bank;
 pub mod board;
 pub mod building;
+pub mod catanatron;
+pub mod chit_layout;
 pub mod development;
 pub mod edge;
+pub mod edition;
+pub mod event;
+#[allow(clippy::module_inception)]
 pub mod game;
+pub mod history;
 pub mod node;
+pub mod phase;
 pub mod player;
 pub mod port;
+pub mod record;
+pub mod replay;
 pub mod resource;
+pub mod snapshot;
+pub mod standing;
 pub mod terrain;
 pub mod tile;
-pub mod trade_offer;
\ No newline at end of file
+pub mod trade_offer;
+pub mod view;
\ No newline at end of file
@@ -0,0 +1,25 @@
+use serde::{Serialize, Deserialize};
+
+// What the game is waiting for next. Every action is only allowed in some phases, see Game::checkAction.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum GamePhase {
+    // The players are placing their starting settlements and roads.
+    #[default]
+    Setup,
+    // The current player has to roll the dice.
+    PreRoll,
+    // A seven was rolled and each player with more than seven cards has to discard half their hand, in turn.
+    MustDiscard,
+    // A seven was rolled and the player who rolled has to move the robber.
+    MoveRobber,
+    // The current player can build, trade and play development cards until they end their turn.
+    MainPhase,
+    // The current player's trade offer is waiting on the other players.
+    TradeNegotiation,
+    // The current player played road building and has roads left to place for free.
+    RoadBuildingPending { roads_left: usize },
+    // In a 5-6 player game, the other players get a chance to build after each turn.
+    SpecialBuilding,
+    // Someone has won.
+    GameOver,
+}
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, VecDeque};
+use rand::Rng;
 use rand::prelude::SliceRandom;
 use serde::{Serialize, Deserialize};
 
@@ -44,11 +45,11 @@ impl Player {
         development_cards.insert(DevelopmentCard::YearOfPlenty, 0);
 
         Player {
-            id: id,
+            id,
             num_unplaced_cities: 4,
             num_unplaced_settlements: 5,
             num_unplaced_roads: 15,
-            resource_cards: resource_cards,
+            resource_cards,
             development_cards: development_cards.clone(),
             development_cards_drawn_this_turn: development_cards.clone(),
             longest_road: false,
@@ -67,75 +68,76 @@ impl Player {
     pub fn removeCardsFromHand(&mut self, resources: HashMap<ResourceCard, usize>) -> bool {
         // Checks if the cards can be removed from the hand.
         for (resource, amount) in resources.iter() {
-            if !(*self.resource_cards.get(resource).unwrap() >= *amount) {
+            if *self.resource_cards.get(resource).unwrap() < *amount {
                 return false;
             }
         }
         // Removes cards from hand.
         for (resource, amount) in resources.iter() {
             self.resource_cards.insert(
-                resource.clone(),
+                *resource,
                 self.resource_cards.get(resource).unwrap() - *amount
             );
         }
         true
     }
 
-    pub fn hasDevelopmentCard(&mut self, development_card: DevelopmentCard) -> bool {
-        return self.development_cards.get(&development_card).is_some() &&
-            *self.development_cards.get(&development_card).unwrap() > 0;
+    pub fn hasDevelopmentCard(&self, development_card: DevelopmentCard) -> bool {
+        return self.development_cards.get(&development_card).is_some_and(|amount| *amount > 0);
     }
 
-    pub fn hasResourceCards(&mut self, resources: HashMap<ResourceCard, usize>) -> bool {
+    pub fn hasResourceCards(&self, resources: HashMap<ResourceCard, usize>) -> bool {
         // Checks if the cards can be removed from the hand.
         for (resource, amount) in resources.iter() {
-            if !(*self.resource_cards.get(resource).unwrap() >= *amount) {
+            if *self.resource_cards.get(resource).unwrap() < *amount {
                 return false;
             }
         }
         true
     }
 
+    // Adds a development card drawn this turn, which can't be played until the player's next turn.
     pub fn addDevelopmentCard(&mut self, development_card: DevelopmentCard) {
         self.development_cards_drawn_this_turn.insert(
-            development_card.clone(),
-            self.development_cards.get(&development_card.clone()).unwrap() + 1
+            development_card,
+            self.development_cards_drawn_this_turn.get(&development_card).unwrap() + 1
         );
     }
 
     pub fn removeDevelopmentCard(&mut self, development_card: DevelopmentCard) {
         self.development_cards.insert(
-            development_card.clone(),
+            development_card,
             self.development_cards.get(&development_card.clone()).unwrap() - 1
         );
     }
 
     pub fn addResourceCards(&mut self, resources: HashMap<ResourceCard, usize>) {
         for (key, value) in resources {
-            self.resource_cards.insert(key.clone(), self.resource_cards.get(&key).unwrap() + value);
+            self.resource_cards.insert(key, self.resource_cards.get(&key).unwrap() + value);
         }
     }
 
-    pub fn stealCard(&mut self) -> Option<ResourceCard> {
+    pub fn stealCard(&mut self, rng: &mut impl Rng) -> Option<ResourceCard> {
+        // Go through the resources in a fixed order so the same rng always steals the same card.
         let mut available_cards = vec![];
-        for (resource, amount) in self.resource_cards.clone().into_iter() {
-            for _ in 0..amount {
-                available_cards.push(resource.clone());
+        for resource in ResourceCard::ALL {
+            for _ in 0..*self.resource_cards.get(&resource).unwrap() {
+                available_cards.push(resource);
             }
         }
 
-        if available_cards.len() < 1 {
+        if available_cards.is_empty() {
             return None;
         }
 
-        let chosen_card = available_cards.choose(&mut rand::thread_rng()).unwrap().clone();
-        self.resource_cards.insert(chosen_card.clone(), self.resource_cards.get(&chosen_card).unwrap() - 1);
+        let chosen_card = *available_cards.choose(rng).unwrap();
+        self.resource_cards.insert(chosen_card, self.resource_cards.get(&chosen_card).unwrap() - 1);
 
         Some(chosen_card)
     }
 
     pub fn removeAllResourcesFromHand(&mut self, resource: ResourceCard) -> usize {
-        let amount_in_hand = *self.resource_cards.get(&resource).unwrap() as usize;
+        let amount_in_hand = *self.resource_cards.get(&resource).unwrap();
         self.resource_cards.insert(resource, 0);
         amount_in_hand
     }
@@ -148,9 +150,39 @@ impl Player {
         self.resource_cards.insert(resource, self.resource_cards.get(&resource).unwrap() + amount);
     }
 
+    pub fn developmentCards(&self) -> &HashMap<DevelopmentCard, usize> {
+        &self.development_cards
+    }
+
+    pub fn developmentCardsDrawnThisTurn(&self) -> &HashMap<DevelopmentCard, usize> {
+        &self.development_cards_drawn_this_turn
+    }
+
+    pub fn numResourceCards(&self) -> usize {
+        self.resource_cards.values().sum()
+    }
+
+    // Returns the number of development cards the player is holding, including ones drawn this turn.
+    pub fn numDevelopmentCards(&self) -> usize {
+        self.development_cards.values().sum::<usize>() + self.development_cards_drawn_this_turn.values().sum::<usize>()
+    }
+
+    // Returns the number of victory point development cards the player is holding.
+    pub fn victoryPointCards(&self) -> usize {
+        self.development_cards.get(&DevelopmentCard::VictoryPoint).unwrap() +
+            self.development_cards_drawn_this_turn.get(&DevelopmentCard::VictoryPoint).unwrap()
+    }
+
+    // Returns the player's victory points, including any victory point cards they are holding.
+    pub fn totalVictoryPoints(&self) -> usize {
+        self.victory_points + self.victoryPointCards()
+    }
+
+    // Makes the development cards drawn this turn playable, at the end of the player's turn.
     pub fn moveDevelopmentCards(&mut self) {
-        for (development_card, amount) in &self.development_cards_drawn_this_turn {
-            self.development_cards.insert(*development_card, *self.development_cards.get(&development_card).unwrap() + amount);
+        for (development_card, amount) in self.development_cards_drawn_this_turn.iter_mut() {
+            self.development_cards.insert(*development_card, *self.development_cards.get(development_card).unwrap() + *amount);
+            *amount = 0;
         }
     }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use serde::{Serialize, Deserialize};
 
-#[derive(Serialize, Deserialize)]
+use crate::game::resource::ResourceCard;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
 pub enum Port {
     ThreeToOne,
     Lumber,
@@ -8,4 +10,18 @@ pub enum Port {
     Wheat,
     Sheep,
     Brick,
-}
\ No newline at end of file
+}
+
+impl Port {
+    // Returns the resource a 2:1 port trades, or None for a 3:1 port which trades any resource.
+    pub fn resource(&self) -> Option<ResourceCard> {
+        match self {
+            Port::ThreeToOne => None,
+            Port::Lumber => Some(ResourceCard::Lumber),
+            Port::Ore => Some(ResourceCard::Ore),
+            Port::Wheat => Some(ResourceCard::Wheat),
+            Port::Sheep => Some(ResourceCard::Sheep),
+            Port::Brick => Some(ResourceCard::Brick),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use serde::{Serialize, Deserialize};
+
+use crate::game::event::Event;
+use crate::game::replay::ReplayError;
+
+// The version of the game record format written by this build. Records before version 3 were played back from a seed
+// and are rejected.
+pub const RECORD_FORMAT_VERSION: u32 = 3;
+
+// A whole game as a single JSON document, to hand a finished game to another program or read it back.
+// The board is a board string (see board::notation) and the events are the same as in a replay. The dice rolled,
+// cards stolen and development cards drawn are listed in the events, so the game is played back from them
+// rather than from a seed. For example {"version":3,"board":"O10,S2,...|3S3WB3LO3","num_players":4,"events":[...]}.
+// Games from Catanatron are read and written with Game::fromCatanatronJson and Game::toCatanatronJson.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct GameRecord {
+    pub version: u32,
+    pub board: String,
+    pub num_players: usize,
+    pub events: Vec<Event>,
+}
+
+#[allow(non_snake_case)]
+impl GameRecord {
+    pub fn toJson(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn fromJson(json: &str) -> Result<Self, ReplayError> {
+        serde_json::from_str(json).map_err(|error| ReplayError::InvalidLine { line: error.line(), message: error.to_string() })
+    }
+}
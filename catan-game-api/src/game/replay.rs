@@ -0,0 +1,83 @@
+use serde::{Serialize, Deserialize};
+
+use crate::game::action_result::ActionError;
+use crate::game::chit_layout::ChitLayout;
+use crate::game::event::Event;
+
+// The version of the replay format written by this build. Older versions are rejected rather than guessed at.
+pub const REPLAY_FORMAT_VERSION: u32 = 2;
+
+// The first line of a replay, with everything needed to set up the game before any event.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReplayHeader {
+    pub version: u32,
+    pub seed: u64,
+    pub num_players: usize,
+    pub chit_layout: ChitLayout,
+    // The board string of the board the game was set up on, when it wasn't made from the chit layout and seed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+}
+
+// Everything that happened in a game, in order. See Game::replay to rebuild the game from it.
+#[derive(Clone, PartialEq)]
+pub struct EventLog {
+    pub header: ReplayHeader,
+    pub events: Vec<Event>,
+}
+
+// Why a replay couldn't be read or played back.
+#[derive(Clone, PartialEq, Debug)]
+pub enum ReplayError {
+    UnsupportedVersion(u32),
+    // A line of the replay isn't valid JSON for the header or an event.
+    InvalidLine { line: usize, message: String },
+    // The action at the given event index was rejected by the game.
+    ActionRejected { index: usize, error: ActionError },
+    // The game produced a different random outcome than the one recorded at the given event index.
+    OutcomeMismatch { index: usize },
+    // The action at the given index of an imported game can't be read, or has no equivalent in this game,
+    // such as a trade between players in a Catanatron game.
+    UnsupportedAction { index: usize, message: String },
+    // The board or number of players the game was set up with isn't valid.
+    InvalidSetup(String),
+}
+
+#[allow(non_snake_case)]
+impl EventLog {
+    // Writes the log as JSON lines, with the header on the first line and one event per line after it.
+    pub fn toJsonLines(&self) -> String {
+        let mut lines = vec![serde_json::to_string(&self.header).unwrap()];
+        lines.extend(self.events.iter().map(|event| serde_json::to_string(event).unwrap()));
+        lines.join("\n") + "\n"
+    }
+
+    pub fn fromJsonLines(json_lines: &str) -> Result<Self, ReplayError> {
+        let mut lines = json_lines.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let invalid_line = |line: usize, error: serde_json::Error| ReplayError::InvalidLine { line: line + 1, message: error.to_string() };
+
+        let Some((line, header)) = lines.next() else {
+            return Err(ReplayError::InvalidLine { line: 1, message: String::from("The replay is empty.") });
+        };
+        let header: ReplayHeader = serde_json::from_str(header).map_err(|error| invalid_line(line, error))?;
+        if header.version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header.version));
+        }
+
+        let events = lines.map(|(line, event)| serde_json::from_str(event).map_err(|error| invalid_line(line, error)))
+            .collect::<Result<Vec<Event>, ReplayError>>()?;
+        Ok(EventLog { header, events })
+    }
+
+    // Returns the log up to the given number of events, to replay a game to an earlier point.
+    pub fn truncated(&self, num_events: usize) -> Self {
+        EventLog {
+            header: self.header.clone(),
+            events: self.events.iter().take(num_events).cloned().collect(),
+        }
+    }
+
+    pub fn numActions(&self) -> usize {
+        self.events.iter().filter(|event| matches!(event, Event::ActionTaken { .. })).count()
+    }
+}
@@ -9,6 +9,16 @@ pub enum ResourceCard {
     Lumber,
 }
 
+impl ResourceCard {
+    pub const ALL: [ResourceCard; 5] = [
+        ResourceCard::Ore,
+        ResourceCard::Wheat,
+        ResourceCard::Sheep,
+        ResourceCard::Brick,
+        ResourceCard::Lumber,
+    ];
+}
+
 // impl ResourceCard {
 //     pub fn value(&self) -> usize {
 //         match *self {
@@ -0,0 +1,14 @@
+use crate::game::game::Game;
+
+// A copy of the whole state of a game, including its random number generator, that the game can be restored to.
+// Nothing is shared with the game it was taken from, so either can change without affecting the other.
+#[derive(Clone)]
+pub struct GameSnapshot {
+    pub(crate) game: Game,
+}
+
+impl From<GameSnapshot> for Game {
+    fn from(snapshot: GameSnapshot) -> Self {
+        snapshot.game
+    }
+}
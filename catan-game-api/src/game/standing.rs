@@ -0,0 +1,8 @@
+use serde::{Serialize, Deserialize};
+
+// A player's place in the final results of a game.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Standing {
+    pub player_id: usize,
+    pub victory_points: usize,
+}
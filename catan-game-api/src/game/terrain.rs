@@ -1,6 +1,9 @@
 
 use serde::{Serialize, Deserialize};
-#[derive(Serialize, Deserialize, PartialEq)]
+
+use super::resource::ResourceCard;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
 pub enum Terrain {
     Plains,
     Forest,
@@ -8,4 +11,18 @@ pub enum Terrain {
     Hills,
     Fields,
     Desert,
-}
\ No newline at end of file
+}
+
+impl Terrain {
+    // Returns the resource the terrain produces, or None for the desert.
+    pub fn resource(&self) -> Option<ResourceCard> {
+        match self {
+            Terrain::Plains => Some(ResourceCard::Sheep),
+            Terrain::Forest => Some(ResourceCard::Lumber),
+            Terrain::Mountains => Some(ResourceCard::Ore),
+            Terrain::Hills => Some(ResourceCard::Brick),
+            Terrain::Fields => Some(ResourceCard::Wheat),
+            Terrain::Desert => None,
+        }
+    }
+}
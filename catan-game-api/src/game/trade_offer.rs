@@ -4,9 +4,53 @@ use serde::{Deserialize, Serialize};
 
 use crate::game::resource::ResourceCard;
 
+// A trade offered by the current player to every other player. The resources are from the offering player's side.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct TradeOffer {
+    pub id: usize,
     pub player_offerer_id: usize,
     pub receiving_resources: HashMap<ResourceCard, usize>,
-    pub giving_resources: HashMap<ResourceCard, usize>
-}
\ No newline at end of file
+    pub giving_resources: HashMap<ResourceCard, usize>,
+    // Each player's response to the offer, indexed by player. The offering player's own response is left pending.
+    pub responses: Vec<TradeResponse>,
+    pub status: TradeStatus,
+}
+
+// Whether a trade offer is still open, and how it was closed if not.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum TradeStatus {
+    Open,
+    // The offering player confirmed the trade with the given player.
+    Completed { player: usize },
+    // The offering player withdrew the offer, or it wasn't settled in time.
+    Withdrawn,
+    // Every other player declined the offer.
+    Declined,
+}
+
+// How a player has responded to a trade offer. A player can change their response until the trade is made.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub enum TradeResponse {
+    Pending,
+    Accepted,
+    Declined,
+    // The player would trade on other terms instead, from the responding player's side.
+    Countered { giving: HashMap<ResourceCard, usize>, receiving: HashMap<ResourceCard, usize> },
+}
+
+#[allow(non_snake_case)]
+impl TradeOffer {
+    pub fn isOpen(&self) -> bool {
+        self.status == TradeStatus::Open
+    }
+
+    // Returns the cards the offering player and the given player would each give if they traded,
+    // on the terms of the offer or of the player's counter-offer. None if the player hasn't agreed to trade.
+    pub fn termsWith(&self, player_id: usize) -> Option<(HashMap<ResourceCard, usize>, HashMap<ResourceCard, usize>)> {
+        match self.responses.get(player_id)? {
+            TradeResponse::Accepted => Some((self.giving_resources.clone(), self.receiving_resources.clone())),
+            TradeResponse::Countered { giving, receiving } => Some((receiving.clone(), giving.clone())),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use serde::Serialize;
+
+use crate::game::bank::Bank;
+use crate::game::board::Board;
+use crate::game::development::DevelopmentCard;
+use crate::game::phase::GamePhase;
+use crate::game::player::Player;
+use crate::game::resource::ResourceCard;
+use crate::game::standing::Standing;
+use crate::game::trade_offer::TradeOffer;
+
+// What a player is allowed to see of the game. Opponents' hands are reduced to counts,
+// and the development card deck is reduced to the number of cards left.
+#[derive(Serialize)]
+pub struct GameView<'a> {
+    pub board: &'a Board,
+    pub bank: BankView,
+    pub players: Vec<PlayerView>,
+    pub turn_number: i32,
+    pub current_player_id: usize,
+    pub turn_player_id: usize,
+    pub players_to_discard: Vec<usize>,
+    // The trade offers made this turn, open or closed, with every response to them.
+    pub trade_offers: Vec<TradeOffer>,
+    // What the game is waiting for next.
+    pub phase: GamePhase,
+    pub previous_dice_roll: usize,
+    pub game_ended: bool,
+    pub winner: Option<usize>,
+    pub final_standings: Vec<Standing>,
+}
+
+#[derive(Serialize)]
+pub struct BankView {
+    pub resource_cards: HashMap<ResourceCard, usize>,
+    pub num_development_cards: usize,
+}
+
+#[derive(Serialize)]
+pub struct PlayerView {
+    pub id: usize,
+    pub num_unplaced_cities: usize,
+    pub num_unplaced_settlements: usize,
+    pub num_unplaced_roads: usize,
+    // The player's cards are only shown to whoever controls the player.
+    pub resource_cards: Option<HashMap<ResourceCard, usize>>,
+    pub development_cards: Option<HashMap<DevelopmentCard, usize>>,
+    pub development_cards_drawn_this_turn: Option<HashMap<DevelopmentCard, usize>>,
+    pub num_resource_cards: usize,
+    pub num_development_cards: usize,
+    pub longest_road: bool,
+    pub largest_army: bool,
+    pub settlement_placements: Vec<usize>,
+    pub road_placements: Vec<usize>,
+    pub city_placements: Vec<usize>,
+    // Victory point cards aren't included until the game ends, since they are kept hidden.
+    pub victory_points: usize,
+    // The number of victory point cards the player holds, shown to whoever controls the player or once the game ends.
+    pub victory_point_cards: Option<usize>,
+    pub num_knights_played: usize,
+    pub longest_road_length: usize,
+}
+
+#[allow(non_snake_case)]
+impl BankView {
+    pub fn new(bank: &Bank) -> Self {
+        BankView {
+            resource_cards: ResourceCard::ALL.iter().map(|resource| (*resource, bank.amountOfResource(*resource))).collect(),
+            num_development_cards: bank.numDevelopmentCardsLeft(),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+impl PlayerView {
+    // Creates the view of a player, showing their cards if revealed.
+    pub fn new(player: &Player, revealed: bool, game_ended: bool) -> Self {
+        PlayerView {
+            id: player.id,
+            num_unplaced_cities: player.num_unplaced_cities,
+            num_unplaced_settlements: player.num_unplaced_settlements,
+            num_unplaced_roads: player.num_unplaced_roads,
+            resource_cards: revealed.then(|| player.resource_cards.clone()),
+            development_cards: revealed.then(|| player.developmentCards().clone()),
+            development_cards_drawn_this_turn: revealed.then(|| player.developmentCardsDrawnThisTurn().clone()),
+            num_resource_cards: player.numResourceCards(),
+            num_development_cards: player.numDevelopmentCards(),
+            longest_road: player.longest_road,
+            largest_army: player.largest_army,
+            settlement_placements: player.settlement_placements.clone(),
+            road_placements: player.road_placements.clone(),
+            city_placements: player.city_placements.clone(),
+            victory_points: if game_ended { player.totalVictoryPoints() } else { player.victory_points },
+            victory_point_cards: (revealed || game_ended).then(|| player.victoryPointCards()),
+            num_knights_played: player.num_knights_played,
+            longest_road_length: player.longest_road_length,
+        }
+    }
+}
@@ -0,0 +1,4 @@
+#![allow(clippy::needless_return)]
+
+pub mod game;
+pub mod registry;
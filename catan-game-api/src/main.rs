@@ -1,28 +1,125 @@
+#![allow(clippy::needless_return)]
+
 use actix_web::{rt, web, App, Error, HttpRequest, HttpResponse, HttpServer};
-use actix_ws::AggregatedMessage;
+use actix_ws::{AggregatedMessage, Session};
 use futures::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde::Serializer;
 // use std::borrow::{Borrow, BorrowMut};
-use std::sync::{LazyLock, Mutex};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
+use std::time::Duration;
 // use serde_json::{ from_str };
 
-mod game;
-use crate::game::game::Game;
-use crate::game::action::Action;
+use catan_game_api::game::view::GameView;
+use catan_game_api::game::action::{Action, ActionMessage};
+use catan_game_api::game::action_result::{ActionResult, StateDelta};
+use catan_game_api::game::chit_layout::ChitLayout;
+use catan_game_api::game::board::Board;
+use catan_game_api::game::catanatron::CatanatronGame;
+use catan_game_api::game::game::Game;
+use catan_game_api::game::record::GameRecord;
+use catan_game_api::registry::{GameRegistry, RegistryError, SharedGame};
+
+static GAMES: LazyLock<MutexWrapper<GameRegistry>> = LazyLock::new(|| MutexWrapper(Mutex::new(GameRegistry::new())));
+// The open websocket of every connection, so players can be told about changes they didn't ask for.
+static SESSIONS: LazyLock<Mutex<HashMap<usize, Session>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+// The latest timeout started for each trade offer, by game id and offer id. Only the latest one withdraws the offer,
+// so an offer brought back by an undo or redo expires on its new deadline rather than its old one.
+static TRADE_OFFER_TIMERS: LazyLock<Mutex<HashMap<(usize, usize), usize>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+static NEXT_TRADE_OFFER_TIMER_ID: AtomicUsize = AtomicUsize::new(0);
 
-static GAME: LazyLock<MutexWrapper<Game>> = LazyLock::new(|| MutexWrapper(Mutex::new(Game::new())));
+// Games that haven't received a command in this long are removed from the registry.
+const ABANDONED_GAME_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+// How often the registry is checked for abandoned games.
+const ABANDONED_GAME_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Trade offers that haven't been settled in this long are withdrawn, so players who don't respond can't hold up the game.
+const TRADE_OFFER_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[derive(Serialize, Deserialize)]
 struct WebSocketCommand {
     command: String,
-    action: Option<Action>,
+    game_id: Option<usize>,
+    // Either a typed action, or an action in the older flat format.
+    action: Option<ActionMessage>,
+    chit_layout: Option<ChitLayout>,
+    seed: Option<u64>,
+    // The seat to take in the game, or the player to act or list legal actions for.
+    player_id: Option<usize>,
+    // The number of players in a new game, four if not given.
+    num_players: Option<usize>,
+    // A board string to set a new game up on, instead of a board made from the chit layout.
+    board: Option<String>,
+    // A game record to import.
+    record: Option<GameRecord>,
+    // A game in Catanatron's game JSON to import instead of a record.
+    catanatron_game: Option<serde_json::Value>,
+    // The format to export a game in, "catanatron" for Catanatron's game JSON or a game record if not given.
+    format: Option<String>,
+    // Whether a new game keeps the history needed to undo actions, which it does if not given.
+    keep_history: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
 struct GameWrapper<'a> {
+    game_id: usize,
     last_action_successful: bool,
-    game: Game<'a>,
+    // What the last action changed, or why it was rejected. Not set for a new game.
+    action_result: Option<ActionResult>,
+    // The game as seen from the connection's seat.
+    game: GameView<'a>,
+}
+
+#[derive(Serialize)]
+struct LegalActionsWrapper {
+    game_id: usize,
+    player_id: usize,
+    legal_actions: Vec<Action>,
+}
+
+#[derive(Serialize)]
+struct RecordWrapper {
+    game_id: usize,
+    record: GameRecord,
+}
+
+#[derive(Serialize)]
+struct CatanatronGameWrapper {
+    game_id: usize,
+    catanatron_game: CatanatronGame,
+}
+
+// Sent to every other connection playing a game when an action, undo or redo changes it, so the other players
+// see trade offers and responses as they are made.
+#[derive(Serialize)]
+struct GameUpdateWrapper<'a> {
+    game_id: usize,
+    // What the action changed, as seen from the connection's seat. Not set for an undo or redo.
+    state_delta: Option<StateDelta>,
+    // The game as seen from the connection's seat.
+    game: GameView<'a>,
+}
+
+// Sent to every connection playing a game when a trade offer is withdrawn for not being settled in time.
+#[derive(Serialize)]
+struct TradeOfferExpiredWrapper<'a> {
+    game_id: usize,
+    expired_trade_offer_id: usize,
+    game: GameView<'a>,
+}
+
+#[derive(Serialize)]
+struct MessageWrapper {
+    game_id: Option<usize>,
+    message: String,
+}
+
+// The games a connection creates and the seats it takes are kept in the registry under its id.
+struct ConnectionState {
+    connection_id: usize,
+    // The game used when a command has no game id.
+    last_game_id: Option<usize>,
 }
 
 pub struct MutexWrapper<T: ?Sized>(pub Mutex<T>);
@@ -42,6 +139,19 @@ impl<T: ?Sized + Serialize> Serialize for MutexWrapper<T> {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()>{
+    // Periodically remove games whose clients have gone away.
+    rt::spawn(async {
+        let mut interval = rt::time::interval(ABANDONED_GAME_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let mut games = GAMES.0.lock().unwrap();
+            let num_removed = games.removeAbandonedGames(ABANDONED_GAME_TIMEOUT);
+            if num_removed > 0 {
+                println!("Removed {} abandoned games, {} games still active.", num_removed, games.numGames());
+            }
+        }
+    });
+
     HttpServer::new(|| {
         App::new()
             .route("/handleMove", web::get().to(move_handler))
@@ -61,40 +171,25 @@ async fn move_handler(req: HttpRequest, stream: web::Payload) -> Result<HttpResp
 
     // start task but don't wait for it
     rt::spawn(async move {
+        let mut connection_state = ConnectionState {
+            connection_id: GAMES.0.lock().unwrap().newConnectionId(),
+            last_game_id: None,
+        };
+        SESSIONS.lock().unwrap().insert(connection_state.connection_id, session.clone());
+
         // receive messages from websocket
         while let Some(msg) = stream.next().await {
             match msg {
                 Ok(AggregatedMessage::Text(text)) => {
-                    // echo text message
-                    // session.text(text).await.unwrap();
                     let action: Result<WebSocketCommand, serde_json::Error> = serde_json::from_str(&text);
                     match action {
                         Ok(ws_command) => {
-                            if ws_command.command == "new_game" {
-                                GAME.0.lock().unwrap().reset();
-                                let game: Game = GAME.0.lock().unwrap().clone();
-                                let game_wrapper = GameWrapper{game, last_action_successful: false};
-                                let game_json = serde_json::to_string(&game_wrapper).expect("Serialization failed.");
-                                // Sends the game and handles the error.
-                                if session.text(game_json.clone()).await.is_err() {
-                                    // There was an error.
-                                    println!("Error sending message.");
-                                }
-                            } else if ws_command.command == "take_action" {
-                                let game: Game = GAME.0.lock().unwrap().clone();
-                                let current_player = GAME.0.lock().unwrap().current_player_id;
-                                let successful = GAME.0.lock().unwrap().takeAction(ws_command.action.unwrap(), current_player);
-                                let game_wrapper = GameWrapper{game, last_action_successful: successful};
-                                let game_json = serde_json::to_string(&game_wrapper).expect("Serialization failed.");
-                                // Sends the game and handles the error.
-                                if session.text(game_json.clone()).await.is_err() {
-                                    // There was an error.
-                                    println!("Error sending message.");
-                                }
-                            }
+                            handleCommand(&mut session, &mut connection_state, ws_command).await;
                         },
                         Err(error) => {
-                            println!("{}", error);
+                            // Tell the client the command couldn't be read, such as a game record that doesn't parse.
+                            let response = serde_json::to_string(&MessageWrapper{game_id: None, message: format!("Invalid command: {}.", error)});
+                            sendResponse(&mut session, response).await;
                         }
                     }
                 }
@@ -104,11 +199,274 @@ async fn move_handler(req: HttpRequest, stream: web::Payload) -> Result<HttpResp
                     session.pong(&msg).await.unwrap();
                 }
 
+                Ok(AggregatedMessage::Close(_)) => {
+                    break;
+                }
+
                 _ => {}
             }
         }
+
+        // The client is gone, so nobody is going to finish its games or play its seats.
+        GAMES.0.lock().unwrap().removeConnection(connection_state.connection_id);
+        SESSIONS.lock().unwrap().remove(&connection_state.connection_id);
     });
 
     // respond immediately with response connected to WS session
     Ok(res)
-}
\ No newline at end of file
+}
+
+#[allow(non_snake_case)]
+async fn handleCommand(session: &mut Session, connection_state: &mut ConnectionState, ws_command: WebSocketCommand) {
+    let connection_id = connection_state.connection_id;
+    let game_id = ws_command.game_id.or(connection_state.last_game_id);
+    // Messages for the other connections playing the game, sent after the response.
+    let mut updates: Vec<(Session, String)> = vec![];
+
+    let response = match ws_command.command.as_str() {
+        "new_game" => {
+            let chit_layout = ws_command.chit_layout.unwrap_or_default();
+            let num_players = ws_command.num_players.unwrap_or(4);
+            let new_game = match &ws_command.board {
+                Some(board_string) => Board::fromBoardString(board_string).and_then(|board| Game::withBoard(board, num_players, ws_command.seed)),
+                None => Game::withPlayers(num_players, chit_layout, ws_command.seed)
+            };
+            let new_game = match new_game {
+                Ok(mut new_game) => {
+                    new_game.setHistoryEnabled(ws_command.keep_history.unwrap_or(true));
+                    new_game
+                },
+                Err(message) => {
+                    let response = serde_json::to_string(&MessageWrapper{game_id: ws_command.game_id, message});
+                    return sendResponse(session, response).await;
+                }
+            };
+
+            // Start the requested game again if this connection created it, otherwise create a new one.
+            let seat = ws_command.player_id;
+            let game_id = match ws_command.game_id {
+                Some(game_id) => GAMES.0.lock().unwrap().resetGame(game_id, new_game, connection_id, seat).map(|_| game_id),
+                None => GAMES.0.lock().unwrap().addGame(new_game, connection_id, seat)
+            };
+            match game_id.and_then(|game_id| playedGame(Some(game_id), connection_id)) {
+                Ok((game_id, game, seat)) => {
+                    connection_state.last_game_id = Some(game_id);
+                    let game = game.lock().unwrap();
+                    serde_json::to_string(&GameWrapper{game_id, game: game.view(seat), last_action_successful: false, action_result: None})
+                },
+                Err(error) => serde_json::to_string(&MessageWrapper{game_id: ws_command.game_id, message: String::from(error.message())})
+            }
+        },
+        "join_game" => {
+            let joined = match (game_id, ws_command.player_id) {
+                (Some(game_id), Some(player_id)) => GAMES.0.lock().unwrap().joinGame(game_id, connection_id, player_id),
+                (None, _) => Err(RegistryError::GameNotFound),
+                (_, None) => Err(RegistryError::SeatUnavailable)
+            };
+            match joined {
+                Ok(game) => {
+                    let game_id = game_id.unwrap();
+                    connection_state.last_game_id = Some(game_id);
+
+                    let game = game.lock().unwrap();
+                    let game = game.view(ws_command.player_id);
+                    serde_json::to_string(&GameWrapper{game_id, game, last_action_successful: false, action_result: None})
+                },
+                Err(error) => serde_json::to_string(&MessageWrapper{game_id, message: String::from(error.message())})
+            }
+        },
+        "take_action" => {
+            match (playedGame(game_id, connection_id), ws_command.action) {
+                (Ok((game_id, shared_game, seat)), Some(action)) => {
+                    // The registry is locked before any game, as everywhere else.
+                    let other_connections = GAMES.0.lock().unwrap().otherConnections(game_id, connection_id);
+                    let mut game = shared_game.lock().unwrap();
+
+                    // A seated connection always acts as its seat, otherwise it acts for the given player,
+                    // such as one responding to a trade offer, or the current player.
+                    let player_id = seat.or(ws_command.player_id).unwrap_or(game.current_player_id);
+                    let trade_offers_before = game.openTradeOfferIds();
+                    let action_result = action.toActions(&game, player_id).and_then(|actions| game.takeActions(actions, player_id));
+                    for trade_offer_id in game.openTradeOfferIds() {
+                        if !trade_offers_before.contains(&trade_offer_id) {
+                            expireTradeOfferLater(game_id, &shared_game, trade_offer_id);
+                        }
+                    }
+                    if let Ok(state_delta) = &action_result {
+                        updates = messagesTo(other_connections, |seat| {
+                            serde_json::to_string(&GameUpdateWrapper{game_id, state_delta: Some(state_delta.view(seat)), game: game.view(seat)})
+                        });
+                    }
+                    serde_json::to_string(&GameWrapper{
+                        game_id,
+                        game: game.view(seat),
+                        last_action_successful: action_result.is_ok(),
+                        action_result: Some(action_result.map(|state_delta| state_delta.view(seat)))
+                    })
+                },
+                (Err(error), _) => serde_json::to_string(&MessageWrapper{game_id, message: String::from(error.message())}),
+                (_, None) => serde_json::to_string(&MessageWrapper{game_id, message: String::from("No action given.")})
+            }
+        },
+        "undo" | "redo" => {
+            match playedGame(game_id, connection_id) {
+                Ok((game_id, shared_game, seat)) => {
+                    let other_connections = GAMES.0.lock().unwrap().otherConnections(game_id, connection_id);
+                    let mut game = shared_game.lock().unwrap();
+                    let is_undo = ws_command.command == "undo";
+                    let trade_offers_before = game.openTradeOfferIds();
+
+                    // A seated player can only take back their own last action, and only while it hasn't shown anything
+                    // hidden, such as the dice or a drawn card. Without a seat, the connection controls every seat.
+                    let allowed = seat.is_none_or(|seat| if is_undo { game.canPlayerUndo(seat) } else { game.canPlayerRedo(seat) });
+                    if !allowed {
+                        serde_json::to_string(&MessageWrapper{game_id: Some(game_id), message: format!("You can't {} that action.", ws_command.command)})
+                    } else {
+                        let successful = if is_undo { game.undo() } else { game.redo() };
+                        // Offers brought back by undoing or redoing get a new timeout, which replaces any they had before.
+                        for trade_offer_id in game.openTradeOfferIds() {
+                            if !trade_offers_before.contains(&trade_offer_id) {
+                                expireTradeOfferLater(game_id, &shared_game, trade_offer_id);
+                            }
+                        }
+                        if successful {
+                            updates = messagesTo(other_connections, |seat| {
+                                serde_json::to_string(&GameUpdateWrapper{game_id, state_delta: None, game: game.view(seat)})
+                            });
+                        }
+                        serde_json::to_string(&GameWrapper{game_id, game: game.view(seat), last_action_successful: successful, action_result: None})
+                    }
+                },
+                Err(error) => serde_json::to_string(&MessageWrapper{game_id, message: String::from(error.message())})
+            }
+        },
+        "import_game" => {
+            let imported = match (&ws_command.record, &ws_command.catanatron_game) {
+                (Some(record), _) => Some(Game::fromRecord(record)),
+                (None, Some(catanatron_game)) => Some(Game::fromCatanatronJson(&catanatron_game.to_string())),
+                (None, None) => None
+            };
+            match imported {
+                Some(Ok(game)) => {
+                    let game_id = GAMES.0.lock().unwrap().addGame(game, connection_id, None).unwrap();
+                    connection_state.last_game_id = Some(game_id);
+
+                    let game = GAMES.0.lock().unwrap().getGame(game_id).unwrap();
+                    let game = game.lock().unwrap();
+                    serde_json::to_string(&GameWrapper{game_id, game: game.view(None), last_action_successful: false, action_result: None})
+                },
+                Some(Err(error)) => serde_json::to_string(&MessageWrapper{game_id: None, message: format!("Invalid game record: {:?}.", error)}),
+                None => serde_json::to_string(&MessageWrapper{game_id: None, message: String::from("No record given.")})
+            }
+        },
+        "export_game" => {
+            match playedGame(game_id, connection_id) {
+                Ok((game_id, game, seat)) => {
+                    let game = game.lock().unwrap();
+                    // The record shows every hand and card drawn, so a seated player only gets it once the game is over.
+                    if seat.is_some() && !game.isGameOver() {
+                        serde_json::to_string(&MessageWrapper{game_id: Some(game_id), message: String::from("The game can't be exported until it ends.")})
+                    } else if ws_command.format.as_deref() == Some("catanatron") {
+                        match CatanatronGame::fromGame(&game) {
+                            Ok(catanatron_game) => serde_json::to_string(&CatanatronGameWrapper{game_id, catanatron_game}),
+                            Err(message) => serde_json::to_string(&MessageWrapper{game_id: Some(game_id), message})
+                        }
+                    } else {
+                        serde_json::to_string(&RecordWrapper{game_id, record: game.toRecord()})
+                    }
+                },
+                Err(error) => serde_json::to_string(&MessageWrapper{game_id, message: String::from(error.message())})
+            }
+        },
+        "legal_actions" => {
+            match playedGame(game_id, connection_id) {
+                Ok((game_id, game, seat)) => {
+                    let game = game.lock().unwrap();
+                    let player_id = seat.or(ws_command.player_id).unwrap_or(game.current_player_id);
+                    serde_json::to_string(&LegalActionsWrapper{game_id, player_id, legal_actions: game.legal_actions(player_id)})
+                },
+                Err(error) => serde_json::to_string(&MessageWrapper{game_id, message: String::from(error.message())})
+            }
+        },
+        "close_game" => {
+            let closed = game_id.ok_or(RegistryError::GameNotFound).and_then(|game_id| GAMES.0.lock().unwrap().removeGame(game_id, connection_id));
+            match closed {
+                Ok(()) => serde_json::to_string(&MessageWrapper{game_id, message: String::from("Game closed.")}),
+                Err(error) => serde_json::to_string(&MessageWrapper{game_id, message: String::from(error.message())})
+            }
+        },
+        _ => serde_json::to_string(&MessageWrapper{game_id, message: format!("Unknown command {}.", ws_command.command)})
+    };
+
+    sendResponse(session, response).await;
+    for (mut session, message) in updates {
+        sendResponse(&mut session, Ok(message)).await;
+    }
+}
+
+// Returns the game the connection is playing, with its id and the seat the connection plays,
+// or why the connection can't play it.
+#[allow(non_snake_case)]
+fn playedGame(game_id: Option<usize>, connection_id: usize) -> Result<(usize, SharedGame, Option<usize>), RegistryError> {
+    let game_id = game_id.ok_or(RegistryError::GameNotFound)?;
+    let mut games = GAMES.0.lock().unwrap();
+    let seat = games.seat(game_id, connection_id)?;
+    let game = games.getGame(game_id).ok_or(RegistryError::GameNotFound)?;
+    Ok((game_id, game, seat))
+}
+
+// Withdraws the trade offer once the timeout has passed, unless it has been settled by then, and tells every connection
+// playing the game. The timer only holds on to the game it was started for, so it does nothing once that game is
+// closed or started again, even if the game id is reused. Nor does it do anything once a later timeout has been started
+// for the same offer.
+#[allow(non_snake_case)]
+fn expireTradeOfferLater(game_id: usize, game: &SharedGame, trade_offer_id: usize) {
+    let game: Weak<Mutex<Game>> = Arc::downgrade(game);
+    let timer_id = NEXT_TRADE_OFFER_TIMER_ID.fetch_add(1, Ordering::Relaxed);
+    TRADE_OFFER_TIMERS.lock().unwrap().insert((game_id, trade_offer_id), timer_id);
+    rt::spawn(async move {
+        rt::time::sleep(TRADE_OFFER_TIMEOUT).await;
+        {
+            let mut timers = TRADE_OFFER_TIMERS.lock().unwrap();
+            if timers.get(&(game_id, trade_offer_id)) != Some(&timer_id) {
+                return;
+            }
+            timers.remove(&(game_id, trade_offer_id));
+        }
+        let Some(game) = game.upgrade() else { return; };
+
+        // The registry is locked before any game, as everywhere else.
+        let connections = GAMES.0.lock().unwrap().connections(game_id);
+        let messages: Vec<(Session, String)> = {
+            let mut game = game.lock().unwrap();
+            if !game.expireTradeOffer(trade_offer_id) {
+                return;
+            }
+            messagesTo(connections, |seat| {
+                serde_json::to_string(&TradeOfferExpiredWrapper{game_id, expired_trade_offer_id: trade_offer_id, game: game.view(seat)})
+            })
+        };
+        for (mut session, message) in messages {
+            sendResponse(&mut session, Ok(message)).await;
+        }
+    });
+}
+
+// Builds a message for each of the connections from the seat it plays, along with the session to send it on.
+// Connections that have closed since are left out.
+#[allow(non_snake_case)]
+fn messagesTo(connections: Vec<(usize, Option<usize>)>, message: impl Fn(Option<usize>) -> Result<String, serde_json::Error>) -> Vec<(Session, String)> {
+    let sessions = SESSIONS.lock().unwrap();
+    connections.into_iter().filter_map(|(connection_id, seat)| {
+        Some((sessions.get(&connection_id)?.clone(), message(seat).expect("Serialization failed.")))
+    }).collect()
+}
+
+// Sends the response and handles the error.
+#[allow(non_snake_case)]
+async fn sendResponse(session: &mut Session, response: Result<String, serde_json::Error>) {
+    if session.text(response.expect("Serialization failed.")).await.is_err() {
+        // There was an error.
+        println!("Error sending message.");
+    }
+}
@@ -1 +0,0 @@
-pub mod game;
\ No newline at end of file
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::game::game::Game;
+
+// A game that is shared between every connection playing it.
+pub type SharedGame = Arc<Mutex<Game>>;
+
+// A game held by the registry, along with who is playing it and the last time a client touched it.
+pub struct GameSession {
+    pub game: SharedGame,
+    // The connection that created the game, which is the only one that can start it again or close it.
+    owner: usize,
+    // The connections playing the game, and the seat each one plays. Only the owner can play without a seat,
+    // controlling every seat.
+    connections: HashMap<usize, Option<usize>>,
+    last_accessed: Instant,
+}
+
+// Why a connection can't do what it asked with a game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RegistryError {
+    GameNotFound,
+    // Only the connection that created the game can start it again or close it.
+    NotOwner,
+    // The connection hasn't created or joined the game.
+    NotInGame,
+    // The seat isn't in the game, or another connection is already playing it.
+    SeatUnavailable,
+}
+
+// Holds every game currently being played on the server, keyed by game id.
+pub struct GameRegistry {
+    games: HashMap<usize, GameSession>,
+    next_game_id: usize,
+    next_connection_id: usize,
+}
+
+#[allow(non_snake_case)]
+impl RegistryError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            RegistryError::GameNotFound => "Game not found.",
+            RegistryError::NotOwner => "Only the connection that created the game can do that.",
+            RegistryError::NotInGame => "Join the game first.",
+            RegistryError::SeatUnavailable => "No valid seat given.",
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+impl GameRegistry {
+    pub fn new() -> Self {
+        GameRegistry {
+            games: HashMap::new(),
+            next_game_id: 0,
+            next_connection_id: 0,
+        }
+    }
+
+    // Returns an id for a new connection, which the games it creates and joins are tracked by.
+    pub fn newConnectionId(&mut self) -> usize {
+        let connection_id = self.next_connection_id;
+        self.next_connection_id += 1;
+        connection_id
+    }
+
+    // Adds a new game, or one imported from a record, created by the given connection and played from the given seat.
+    // Returns the game's id.
+    pub fn addGame(&mut self, game: Game, owner: usize, seat: Option<usize>) -> Result<usize, RegistryError> {
+        if seat.is_some_and(|seat| seat >= game.numPlayers()) {
+            return Err(RegistryError::SeatUnavailable);
+        }
+
+        let game_id = self.next_game_id;
+        self.next_game_id += 1;
+        self.games.insert(game_id, GameSession {
+            game: Arc::new(Mutex::new(game)),
+            owner,
+            connections: HashMap::from([(owner, seat)]),
+            last_accessed: Instant::now(),
+        });
+        Ok(game_id)
+    }
+
+    // Starts the game again as the given game, if the connection created it. The other players are unseated,
+    // and the game is replaced rather than changed in place, so nothing still holding the old game affects the new one.
+    pub fn resetGame(&mut self, game_id: usize, game: Game, connection_id: usize, seat: Option<usize>) -> Result<(), RegistryError> {
+        let session = self.games.get_mut(&game_id).ok_or(RegistryError::GameNotFound)?;
+        if session.owner != connection_id {
+            return Err(RegistryError::NotOwner);
+        }
+        if seat.is_some_and(|seat| seat >= game.numPlayers()) {
+            return Err(RegistryError::SeatUnavailable);
+        }
+
+        session.game = Arc::new(Mutex::new(game));
+        session.connections = HashMap::from([(connection_id, seat)]);
+        session.last_accessed = Instant::now();
+        Ok(())
+    }
+
+    // Seats the connection in the game, unless another connection is already playing that seat.
+    pub fn joinGame(&mut self, game_id: usize, connection_id: usize, seat: usize) -> Result<SharedGame, RegistryError> {
+        let session = self.games.get_mut(&game_id).ok_or(RegistryError::GameNotFound)?;
+        let seat_taken = session.connections.iter().any(|(other_id, other_seat)| *other_id != connection_id && *other_seat == Some(seat));
+        if seat >= session.game.lock().unwrap().numPlayers() || seat_taken {
+            return Err(RegistryError::SeatUnavailable);
+        }
+
+        session.connections.insert(connection_id, Some(seat));
+        session.last_accessed = Instant::now();
+        Ok(session.game.clone())
+    }
+
+    // Returns the game with the given id, marking it as recently used.
+    pub fn getGame(&mut self, game_id: usize) -> Option<SharedGame> {
+        let session = self.games.get_mut(&game_id)?;
+        session.last_accessed = Instant::now();
+        Some(session.game.clone())
+    }
+
+    // Returns the seat the connection plays in the game, or None if it plays every seat.
+    pub fn seat(&self, game_id: usize, connection_id: usize) -> Result<Option<usize>, RegistryError> {
+        let session = self.games.get(&game_id).ok_or(RegistryError::GameNotFound)?;
+        session.connections.get(&connection_id).copied().ok_or(RegistryError::NotInGame)
+    }
+
+    // Returns every connection playing the game, with the seat each one plays.
+    pub fn connections(&self, game_id: usize) -> Vec<(usize, Option<usize>)> {
+        self.games.get(&game_id).map_or(vec![], |session| {
+            session.connections.iter().map(|(connection_id, seat)| (*connection_id, *seat)).collect()
+        })
+    }
+
+    // Returns every connection playing the game other than the given one, with the seat each one plays,
+    // so they can be told about a change the given connection made.
+    pub fn otherConnections(&self, game_id: usize, connection_id: usize) -> Vec<(usize, Option<usize>)> {
+        self.connections(game_id).into_iter().filter(|(other_id, _)| *other_id != connection_id).collect()
+    }
+
+    // Removes the game with the given id, if the connection created it.
+    pub fn removeGame(&mut self, game_id: usize, connection_id: usize) -> Result<(), RegistryError> {
+        let session = self.games.get(&game_id).ok_or(RegistryError::GameNotFound)?;
+        if session.owner != connection_id {
+            return Err(RegistryError::NotOwner);
+        }
+        self.games.remove(&game_id);
+        Ok(())
+    }
+
+    // Removes the games a connection created and frees the seats it took, once the connection has closed.
+    // Returns the number of games removed.
+    pub fn removeConnection(&mut self, connection_id: usize) -> usize {
+        let num_games = self.games.len();
+        self.games.retain(|_, session| session.owner != connection_id);
+        for session in self.games.values_mut() {
+            session.connections.remove(&connection_id);
+        }
+        num_games - self.games.len()
+    }
+
+    // Removes every game that has not been used within the given duration, returns the number removed.
+    pub fn removeAbandonedGames(&mut self, max_idle_time: Duration) -> usize {
+        let num_games = self.games.len();
+        self.games.retain(|_, session| session.last_accessed.elapsed() < max_idle_time);
+        num_games - self.games.len()
+    }
+
+    pub fn numGames(&self) -> usize {
+        self.games.len()
+    }
+}
+
+impl Default for GameRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
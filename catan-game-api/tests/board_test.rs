@@ -0,0 +1,287 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use catan_game_api::game::board::Board;
+use catan_game_api::game::board::catanatron::{CatanatronEdgeRef, CatanatronTile, CatanatronTileEntry};
+use catan_game_api::game::board::hex::{self, HexCoord, NodeCoord, EdgeCoord};
+use catan_game_api::game::board::topology::Topology;
+use catan_game_api::game::chit_layout::ChitLayout;
+use catan_game_api::game::edition::Edition;
+use catan_game_api::game::terrain::Terrain;
+use catan_game_api::game::building::Building;
+
+fn chits(board: &Board) -> Vec<i32> {
+    board.tiles.iter().map(|tile| tile.chit).collect()
+}
+
+#[test]
+fn test_chits_placed_on_land_tiles() {
+    for chit_layout in [ChitLayout::Random, ChitLayout::Spiral, ChitLayout::Balanced] {
+        let board = Board::new(Edition::Base, chit_layout, &mut rand::thread_rng());
+        let mut land_chits = vec![];
+        for tile in &board.tiles {
+            if tile.terrain == Terrain::Desert {
+                assert_eq!(tile.chit, 0);
+            } else {
+                land_chits.push(tile.chit);
+            }
+        }
+        land_chits.sort();
+        assert_eq!(land_chits, vec![2, 3, 3, 4, 4, 5, 5, 6, 6, 8, 8, 9, 9, 10, 10, 11, 11, 12]);
+    }
+}
+
+#[test]
+fn test_balanced_chits_not_adjacent() {
+    // Tiles that share an edge, using the row layout of 3, 4, 5, 4, 3 tiles.
+    let adjacent_tiles = [
+        (0, 1), (1, 2), (3, 4), (4, 5), (5, 6), (7, 8), (8, 9), (9, 10), (10, 11), (12, 13), (13, 14), (14, 15), (16, 17), (17, 18),
+        (0, 3), (0, 4), (1, 4), (1, 5), (2, 5), (2, 6),
+        (3, 7), (3, 8), (4, 8), (4, 9), (5, 9), (5, 10), (6, 10), (6, 11),
+        (7, 12), (8, 12), (8, 13), (9, 13), (9, 14), (10, 14), (10, 15), (11, 15),
+        (12, 16), (13, 16), (13, 17), (14, 17), (14, 18), (15, 18),
+    ];
+    for _ in 0..20 {
+        let chits = chits(&Board::new(Edition::Base, ChitLayout::Balanced, &mut rand::thread_rng()));
+        for (tile1, tile2) in adjacent_tiles {
+            let is_red = |chit: i32| chit == 6 || chit == 8;
+            assert!(!(is_red(chits[tile1]) && is_red(chits[tile2])));
+        }
+    }
+}
+
+#[test]
+fn test_same_seed_same_board() {
+    let board1 = Board::new(Edition::Base, ChitLayout::Random, &mut StdRng::seed_from_u64(7));
+    let board2 = Board::new(Edition::Base, ChitLayout::Random, &mut StdRng::seed_from_u64(7));
+    assert_eq!(serde_json::to_value(board1).unwrap(), serde_json::to_value(board2).unwrap());
+}
+
+#[test]
+fn test_board_topology_is_connected() {
+    let board = Board::new(Edition::Base, ChitLayout::Random, &mut StdRng::seed_from_u64(3));
+    assert_eq!(board.nodes.len(), 54);
+    assert_eq!(board.edges.len(), 72);
+    assert!((0..board.edges.len()).all(|edge| board.edgeNodes(edge).len() == 2));
+    assert!((0..board.tiles.len()).all(|tile| board.tileNodes(tile).len() == 6));
+
+    // Every node can be reached from node 0 by following edges.
+    let mut reached = vec![0];
+    let mut unvisited = vec![0];
+    while let Some(node) = unvisited.pop() {
+        for &edge in board.nodeEdges(node) {
+            for other_node in board.edgeNodes(edge) {
+                if !reached.contains(&other_node) {
+                    reached.push(other_node);
+                    unvisited.push(other_node);
+                }
+            }
+        }
+    }
+    assert_eq!(reached.len(), 54);
+}
+
+#[test]
+fn test_topology_tables_checked() {
+    // A single tile, with node i between edges i - 1 and i, and edge i joining nodes i and i + 1.
+    let ring = |first: usize| -> Vec<Vec<usize>> { (0..6).map(|i| vec![first + (i + 5) % 6, first + i]).collect() };
+    let tile = [0, 1, 2, 3, 4, 5];
+    let topology = Topology::fromTables(ring(0), vec![tile], vec![(0, 1)], vec![vec![0]]).unwrap();
+    assert_eq!((topology.numTiles(), topology.numNodes(), topology.numEdges()), (1, 6, 6));
+    assert_eq!(topology.edge_nodes[0], [0, 1]);
+
+    let error = |node_edges, tile_nodes, port_nodes, spiral_rings| Topology::fromTables(node_edges, tile_nodes, port_nodes, spiral_rings).err().unwrap();
+    let mut dead_end = ring(0);
+    dead_end[0] = vec![5];
+    assert_eq!(error(dead_end, vec![tile], vec![], vec![vec![0]]), "Node 0 touches 1 edges.");
+
+    let mut three_nodes = ring(0);
+    three_nodes[3].push(0);
+    assert_eq!(error(three_nodes, vec![tile], vec![], vec![vec![0]]), "Edge 0 joins 3 nodes.");
+
+    assert_eq!(error(ring(0), vec![[0, 1, 2, 3, 4, 4]], vec![], vec![vec![0]]), "Tile 0 has an invalid or repeated node 4.");
+    assert_eq!(error(ring(0), vec![[0, 1, 2, 3, 4, 6]], vec![], vec![vec![0]]), "Tile 0 has an invalid or repeated node 6.");
+    assert_eq!(error(ring(0), vec![tile; 4], vec![], vec![vec![0, 1, 2, 3]]), "Node 0 is on 4 tiles.");
+    assert_eq!(error([ring(0), ring(6)].concat(), vec![tile], vec![], vec![vec![0]]), "Node 6 is on 0 tiles.");
+
+    // Ports have to be on an edge with a node that isn't surrounded by tiles.
+    assert_eq!(error(ring(0), vec![tile], vec![(0, 2)], vec![vec![0]]), "The port on nodes 0 and 2 is not on the coast.");
+    assert_eq!(error(ring(0), vec![tile; 3], vec![(0, 1)], vec![vec![0, 1, 2]]), "The port on nodes 0 and 1 is not on the coast.");
+
+    assert_eq!(error(ring(0), vec![tile], vec![], vec![vec![]]), "The spiral doesn't go through every tile once.");
+    assert_eq!(error(ring(0), vec![tile], vec![], vec![vec![0], vec![0]]), "The spiral doesn't go through every tile once.");
+}
+
+#[test]
+fn test_hex_coordinates_match_board_indices() {
+    let sorted = |mut indices: Vec<usize>| { indices.sort(); indices };
+
+    for edition in [Edition::Base, Edition::FiveSixPlayer] {
+        let board = Board::new(edition, ChitLayout::Random, &mut StdRng::seed_from_u64(3));
+        let (num_tiles, num_nodes, num_edges) = (board.tiles.len(), board.nodes.len(), board.edges.len());
+        for tile in 0..num_tiles {
+            assert_eq!(HexCoord::fromIndex(tile, edition).unwrap().toIndex(edition), Some(tile));
+            assert_eq!(sorted(hex::tile_nodes(tile, edition)), sorted(board.tileNodes(tile).to_vec()));
+        }
+        for node in 0..num_nodes {
+            assert_eq!(NodeCoord::fromIndex(node, edition).unwrap().toIndex(edition), Some(node));
+            assert_eq!(sorted(hex::node_tiles(node, edition)), sorted(board.nodeTiles(node).to_vec()));
+            assert_eq!(sorted(hex::node_edges(node, edition)), sorted(board.nodeEdges(node).to_vec()));
+            for other_node in hex::node_nodes(node, edition) {
+                assert!(hex::node_nodes(other_node, edition).contains(&node));
+            }
+        }
+        for edge in 0..num_edges {
+            assert_eq!(EdgeCoord::fromIndex(edge, edition).unwrap().toIndex(edition), Some(edge));
+            assert_eq!(sorted(hex::edge_nodes(edge, edition)), sorted(board.edgeNodes(edge).to_vec()));
+        }
+        assert_eq!(HexCoord::fromIndex(num_tiles, edition), None);
+        assert_eq!(NodeCoord::fromIndex(num_nodes, edition), None);
+        assert_eq!(EdgeCoord::fromIndex(num_edges, edition), None);
+    }
+
+    // The 5-6 player board reaches a ring further along its long sides.
+    assert!(!HexCoord::new(3, -1).isOnBoard(Edition::Base));
+    assert!(HexCoord::new(3, -1).isOnBoard(Edition::FiveSixPlayer));
+}
+
+#[test]
+fn test_hex_pixel_layout() {
+    let center = HexCoord::new(0, 0);
+    assert_eq!(center.toIndex(Edition::Base), Some(9));
+    assert_eq!(center.toPixel(10.0), (0.0, 0.0));
+
+    for tile in 0..19 {
+        let hex = HexCoord::fromIndex(tile, Edition::Base).unwrap();
+        let (x, y) = hex.toPixel(10.0);
+        assert_eq!(HexCoord::fromPixel(x + 3.0, y - 4.0, 10.0), hex);
+
+        // Every corner is one size away from the center, and next to the corners on either side.
+        let nodes = hex.nodes();
+        for (i, node) in nodes.iter().enumerate() {
+            let (node_x, node_y) = node.toPixel(10.0);
+            assert!(((node_x - x).hypot(node_y - y) - 10.0).abs() < 1e-9);
+            assert!(node.adjacentNodes().contains(&nodes[(i + 1) % 6]));
+        }
+    }
+}
+
+#[test]
+fn test_cloned_board_is_independent() {
+    let mut board = Board::new(Edition::Base, ChitLayout::Random, &mut StdRng::seed_from_u64(4));
+    let copy = board.clone();
+
+    board.placeInitialSettlement(Building::Settlement(0, 0)).unwrap();
+    board.placeInitialRoad(Building::Road(0, 0), 0).unwrap();
+    board.tiles[0].chit += 1;
+
+    assert_eq!(copy.nodeBuilding(0), None);
+    assert_eq!(copy.edgeBuilding(0), None);
+    assert_eq!(copy.tiles[0].chit + 1, board.tiles[0].chit);
+    assert_eq!(board.nodeBuilding(0), Some(Building::Settlement(0, 0)));
+}
+
+#[test]
+fn test_board_string_round_trip() {
+    let board_string = "O10,S2,L9,W12,B6,S4,B10,W9,L11,D,L3,O8,L8,O3,W4,S5,B5,W6,S11|3S3WB3LO3";
+    let board = Board::fromBoardString(board_string).unwrap();
+    assert_eq!(board.tiles[0].terrain, Terrain::Mountains);
+    assert_eq!(board.tiles[0].chit, 10);
+    assert_eq!(board.tiles[9].terrain, Terrain::Desert);
+    assert_eq!(board.robber, 9);
+    assert_eq!(board.toBoardString(), "O10,S2,L9,W12,B6,S4,B10,W9,L11,D*,L3,O8,L8,O3,W4,S5,B5,W6,S11|3S3WB3LO3");
+
+    for chit_layout in [ChitLayout::Random, ChitLayout::Spiral, ChitLayout::Balanced] {
+        let board = Board::new(Edition::Base, chit_layout, &mut rand::thread_rng());
+        let read_board = Board::fromBoardString(&board.toBoardString()).unwrap();
+        assert_eq!(serde_json::to_value(read_board).unwrap(), serde_json::to_value(board).unwrap());
+    }
+
+    assert!(Board::fromBoardString("O10,S2,L9|3S3WB3LO3").is_err());
+    assert!(Board::fromBoardString("O7,S2,L9,W12,B6,S4,B10,W9,L11,D,L3,O8,L8,O3,W4,S5,B5,W6,S11|3S3WB3LO3").is_err());
+    assert!(Board::fromBoardString("O10,S2,L9,W12,B6,S4,B10,W9,L11,D,L3,O8,L8,O3,W4,S5,B5,W6,S11|3S3WB3LO").is_err());
+    assert!(Board::fromBoardString("O10,S2,L9,W12,B6,S4,B10,W9,L11,D,L3,O8,L8,O3,W4,S5,B5,W6,S11").is_err());
+}
+
+#[test]
+fn test_catanatron_map() {
+    let game: serde_json::Value = serde_json::from_str(include_str!("data/catanatron_game.json")).unwrap();
+    let tiles: Vec<CatanatronTileEntry> = serde_json::from_value(game["tiles"].clone()).unwrap();
+    let board = Board::fromCatanatronTiles(&tiles).unwrap();
+    assert_eq!(board.toBoardString(), "O3,L3,W8,L4,S6,B9,S4,W8,W9,B5,O2,B6,L11,S10,O11,D*,S5,L12,W10|SBL3333WO");
+
+    // The ports are written where Catanatron's base map puts them, in port order.
+    let written = board.toCatanatronTiles().unwrap();
+    let ports: Vec<([i32; 3], CatanatronEdgeRef)> = written.iter().filter_map(|entry| match entry.tile {
+        CatanatronTile::Port { direction, .. } => Some((entry.coordinate, direction)),
+        _ => None,
+    }).collect();
+    assert_eq!(ports, [
+        ([0, 3, -3], CatanatronEdgeRef::Southeast),
+        ([2, 1, -3], CatanatronEdgeRef::Southwest),
+        ([3, -1, -2], CatanatronEdgeRef::Southwest),
+        ([-2, 3, -1], CatanatronEdgeRef::East),
+        ([3, -3, 0], CatanatronEdgeRef::West),
+        ([-3, 2, 1], CatanatronEdgeRef::East),
+        ([1, -3, 2], CatanatronEdgeRef::Northwest),
+        ([-3, 0, 3], CatanatronEdgeRef::Northeast),
+        ([-1, -2, 3], CatanatronEdgeRef::Northwest),
+    ]);
+    assert_eq!(written.len(), 37);
+    assert_eq!(Board::fromCatanatronTiles(&written).unwrap().toBoardString(), board.toBoardString());
+
+    for chit_layout in [ChitLayout::Random, ChitLayout::Spiral, ChitLayout::Balanced] {
+        let board = Board::new(Edition::Base, chit_layout, &mut rand::thread_rng());
+        let read_board = Board::fromCatanatronTiles(&board.toCatanatronTiles().unwrap()).unwrap();
+        assert_eq!(serde_json::to_value(read_board).unwrap(), serde_json::to_value(board).unwrap());
+    }
+
+    // A port that isn't at one of the port positions, a missing tile or a 5-6 player board are rejected.
+    let mut moved_port = tiles.clone();
+    let port = moved_port.iter_mut().find(|entry| matches!(entry.tile, CatanatronTile::Port { .. })).unwrap();
+    if let CatanatronTile::Port { direction, .. } = &mut port.tile {
+        *direction = if *direction == CatanatronEdgeRef::West { CatanatronEdgeRef::East } else { CatanatronEdgeRef::West };
+    }
+    assert!(Board::fromCatanatronTiles(&moved_port).is_err());
+    assert!(Board::fromCatanatronTiles(&tiles[1..]).is_err());
+    assert!(Board::new(Edition::FiveSixPlayer, ChitLayout::Balanced, &mut rand::thread_rng()).toCatanatronTiles().is_err());
+}
+
+#[test]
+fn test_five_six_player_board() {
+    for chit_layout in [ChitLayout::Random, ChitLayout::Spiral, ChitLayout::Balanced] {
+        let board = Board::new(Edition::FiveSixPlayer, chit_layout, &mut rand::thread_rng());
+        assert_eq!(board.tiles.len(), 30);
+        assert_eq!(board.ports.len(), 11);
+        assert_eq!(board.tiles.iter().filter(|tile| tile.terrain == Terrain::Desert).count(), 2);
+        assert_eq!(board.tiles[board.robber].terrain, Terrain::Desert);
+        assert!(board.tiles.iter().all(|tile| (tile.terrain == Terrain::Desert) == (tile.chit == 0)));
+
+        // Every node can be reached from node 0 by following edges.
+        let mut reached = vec![0];
+        let mut unvisited = vec![0];
+        while let Some(node) = unvisited.pop() {
+            for &edge in board.nodeEdges(node) {
+                for other_node in board.edgeNodes(edge) {
+                    if !reached.contains(&other_node) {
+                        reached.push(other_node);
+                        unvisited.push(other_node);
+                    }
+                }
+            }
+        }
+        assert_eq!(reached.len(), board.nodes.len());
+
+        // The ports are in the same places on every board, with the base board's ports along the top.
+        assert_eq!(board.port_node_mapping[..5], [(11, 16), (0, 3), (1, 5), (10, 15), (26, 32)]);
+
+        // No two ports share a node.
+        let mut port_nodes: Vec<usize> = board.port_node_mapping.iter().flat_map(|(node1, node2)| [*node1, *node2]).collect();
+        port_nodes.sort();
+        port_nodes.dedup();
+        assert_eq!(port_nodes.len(), 22);
+
+        let read_board = Board::fromBoardString(&board.toBoardString()).unwrap();
+        assert_eq!(serde_json::to_value(read_board).unwrap(), serde_json::to_value(board).unwrap());
+    }
+}
@@ -1,4 +1,1184 @@
+use std::collections::HashMap;
+
+use catan_game_api::game::action::{Action, ActionMessage, ActionType, FlatAction};
+use catan_game_api::game::action_result::{ActionError, StateDelta};
+use catan_game_api::game::board::Board;
+use catan_game_api::game::chit_layout::ChitLayout;
+use catan_game_api::game::development::DevelopmentCard;
+use catan_game_api::game::event::Event;
+use catan_game_api::game::game::Game;
+use catan_game_api::game::history::MAX_UNDO_ACTIONS;
+use catan_game_api::game::phase::GamePhase;
+use catan_game_api::game::record::GameRecord;
+use catan_game_api::game::replay::{EventLog, ReplayError};
+use catan_game_api::game::resource::ResourceCard;
+use catan_game_api::game::terrain::Terrain;
+use catan_game_api::game::trade_offer::{TradeResponse, TradeStatus};
+
+// Sets up a game with a player for each hand the same way every time, and skips to the first player's main phase
+// with the given hands. The edit can change anything else in the serialized game before it is read back,
+// such as the development card deck. The game can't be replayed from its seed, since it was changed after setup.
+fn fixed_game(hands: &[&[(ResourceCard, usize)]], edit: impl FnOnce(&mut serde_json::Value)) -> Game {
+    let mut game = Game::withPlayers(hands.len(), ChitLayout::Balanced, Some(100)).unwrap();
+    while game.isSetupPhase() {
+        let player_id = game.current_player_id;
+        game.takeAction(game.legal_actions(player_id)[0].clone(), player_id).ok().unwrap();
+    }
+
+    // The cards taken out of the bank or put back make up the difference with the hands from setup.
+    let mut state = serde_json::to_value(&game).unwrap();
+    state["phase"] = serde_json::json!("MainPhase");
+    for resource in ResourceCard::ALL {
+        let key = serde_json::to_value(resource).unwrap().as_str().unwrap().to_string();
+        let mut total = state["bank"]["resource_cards"][&key].as_u64().unwrap() as usize;
+        for (player_id, hand) in hands.iter().enumerate() {
+            total += state["players"][player_id]["resource_cards"][&key].as_u64().unwrap() as usize;
+            let amount = hand.iter().filter(|(card, _)| *card == resource).map(|(_, amount)| amount).sum::<usize>();
+            state["players"][player_id]["resource_cards"][&key] = serde_json::json!(amount);
+            total -= amount;
+        }
+        state["bank"]["resource_cards"][&key] = serde_json::json!(total);
+    }
+    edit(&mut state);
+    serde_json::from_value(state).unwrap()
+}
+
+// Rolls the dice for the player until they show the number, undoing every other roll. Returns what the roll changed.
+fn roll_until(game: &mut Game, player_id: usize, number: usize) -> StateDelta {
+    loop {
+        let delta = game.takeAction(Action::RollDice, player_id).ok().unwrap();
+        if delta.dice_roll == Some(number) {
+            return delta;
+        }
+        game.undo();
+    }
+}
+
 #[test]
 fn test_initial_placements() {
-    
-}
\ No newline at end of file
+    let mut game = Game::new(ChitLayout::Balanced, Some(2));
+
+    // Players place in snake order, each placing a settlement and then a road touching it.
+    for player_id in [0, 1, 2, 3, 3, 2, 1, 0] {
+        assert!(game.isSetupPhase());
+        assert_eq!(game.current_player_id, player_id);
+        let settlement = game.legal_actions(player_id)[0].clone();
+        game.takeAction(settlement.clone(), player_id).ok().unwrap();
+        let Action::BuildSettlement { node } = settlement else { panic!("Expected a settlement.") };
+
+        let view = game.view(None);
+        let player = &view.players[player_id];
+        let num_cards = player.num_resource_cards;
+        if let [first_settlement, _] = player.settlement_placements[..] {
+            // The second settlement collects a resource from each tile around it.
+            let num_producing_tiles = view.board.nodeTiles(node).iter().filter(|tile| view.board.tiles[**tile].terrain.resource().is_some()).count();
+            assert_eq!(num_cards, num_producing_tiles);
+
+            // The second road can't be built off the first settlement.
+            let first_road = player.road_placements[0];
+            let other_edge = view.board.nodeEdges(first_settlement).iter().copied().find(|edge| *edge != first_road).unwrap();
+            assert_eq!(game.takeAction(Action::BuildRoad { edge: other_edge }, player_id).err(), Some(ActionError::NotConnected));
+        } else {
+            assert_eq!(num_cards, 0);
+        }
+
+        let road = game.legal_actions(player_id)[0].clone();
+        game.takeAction(road, player_id).ok().unwrap();
+    }
+
+    // Setup takes 16 actions, and then the first player takes the first turn.
+    assert!(!game.isSetupPhase());
+    assert_eq!(game.view(None).turn_number, 16);
+    assert_eq!(game.current_player_id, 0);
+    assert!(game.takeAction(Action::RollDice, 0).is_ok());
+}
+
+#[test]
+fn test_same_seed_same_game() {
+    let game1 = Game::new(ChitLayout::Balanced, Some(1234));
+    let game2 = Game::new(ChitLayout::Balanced, Some(1234));
+    assert_eq!(game1.seed, 1234);
+    assert_eq!(serde_json::to_value(&game1).unwrap(), serde_json::to_value(&game2).unwrap());
+}
+
+#[test]
+fn test_action_rejection_reasons() {
+    let mut game = Game::new(ChitLayout::Balanced, Some(1));
+    let settlement = |node| Action::BuildSettlement { node };
+
+    assert_eq!(game.takeAction(settlement(0), 1).err(), Some(ActionError::NotYourTurn));
+    assert_eq!(game.takeAction(settlement(54), 0).err(), Some(ActionError::InvalidPosition));
+
+    let delta = game.takeAction(settlement(0), 0).ok().unwrap();
+    assert_eq!(delta.players.len(), 1);
+    assert_eq!(delta.players[0].settlements_placed, vec![0]);
+    assert_eq!(delta.players[0].victory_point_change, 1);
+
+    assert_eq!(game.takeAction(settlement(4), 0).err(), Some(ActionError::WrongPhase));
+}
+
+#[test]
+fn test_state_delta_hides_victory_point_cards() {
+    let hand: &[(ResourceCard, usize)] = &[(ResourceCard::Ore, 1), (ResourceCard::Wheat, 1), (ResourceCard::Sheep, 1)];
+    let mut game = fixed_game(&[hand, &[], &[], &[]], |state| {
+        let pointer = state["bank"]["development_card_pointer"].as_u64().unwrap() as usize;
+        state["bank"]["development_cards"][pointer] = serde_json::json!("VictoryPoint");
+    });
+
+    // Only the player who drew a victory point card sees the point it is worth.
+    let delta = game.takeAction(Action::BuyDevelopmentCard, 0).ok().unwrap();
+    assert_eq!(delta.view(Some(0)).players[0].victory_point_change, 1);
+    assert_eq!(delta.view(None).players[0].victory_point_change, 1);
+    assert_eq!(delta.view(Some(1)).players[0].victory_point_change, 0);
+    assert_eq!(delta.view(Some(1)).players[0].development_card_change, 1);
+}
+
+#[test]
+fn test_legal_actions() {
+    let mut game = Game::new(ChitLayout::Balanced, Some(5));
+
+    // Only initial settlements can be placed at the start, and only by the first player.
+    let legal_actions = game.legal_actions(0);
+    assert_eq!(legal_actions.len(), 54);
+    assert!(legal_actions.iter().all(|action| matches!(action, Action::BuildSettlement { .. })));
+    assert!(game.legal_actions(1).is_empty());
+
+    // Once the settlement is placed, only the roads touching it are legal.
+    game.takeAction(legal_actions[0].clone(), 0).ok().unwrap();
+    let legal_actions = game.legal_actions(0);
+    assert!(!legal_actions.is_empty());
+    assert!(legal_actions.iter().all(|action| matches!(action, Action::BuildRoad { .. })));
+
+    // Every legal action is accepted when taken.
+    for _ in 0..200 {
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        if legal_actions.is_empty() {
+            break;
+        }
+        let action = legal_actions[legal_actions.len() / 2].clone();
+        assert!(game.takeAction(action, player_id).is_ok());
+    }
+}
+
+#[test]
+fn test_flat_action_conversion() {
+    let flat_action = |action_type, action_metadata| Vec::<Action>::try_from(FlatAction { action_type, action_metadata });
+
+    assert!(flat_action(ActionType::PlayRoad, [12, 0, 0, 0, 0, 0, 0, 0, 0, 0]) == Ok(vec![Action::BuildRoad { edge: 12 }]));
+    assert!(flat_action(ActionType::PlaceRobber, [4, 2, 0, 0, 0, 0, 0, 0, 0, 0]) == Ok(vec![Action::MoveRobber { tile: 4, victim: Some(2) }]));
+    assert!(flat_action(ActionType::PlayDevelopmentCard, [1, 3, 0, 0, 0, 0, 0, 0, 0, 0]) == Ok(vec![Action::PlayMonopoly { resource: ResourceCard::Brick }]));
+
+    let trade = flat_action(ActionType::OfferTrade, [0, 0, 2, 0, 0, 1, 0, 0, 0, 0]);
+    assert!(trade == Ok(vec![Action::OfferTrade {
+        giving: HashMap::from([(ResourceCard::Sheep, 2)]),
+        receiving: HashMap::from([(ResourceCard::Ore, 1)])
+    }]));
+
+    // Road building is played along with the two roads it places.
+    let road_building = flat_action(ActionType::PlayDevelopmentCard, [2, 30, 31, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(road_building == Ok(vec![Action::PlayRoadBuilding, Action::BuildRoad { edge: 30 }, Action::BuildRoad { edge: 31 }]));
+
+    // Victory point cards can't be played, and resources must exist.
+    assert!(flat_action(ActionType::PlayDevelopmentCard, [3, 0, 0, 0, 0, 0, 0, 0, 0, 0]) == Err(ActionError::InvalidDevelopmentCard));
+    assert!(flat_action(ActionType::PlayDevelopmentCard, [4, 0, 5, 0, 0, 0, 0, 0, 0, 0]) == Err(ActionError::InvalidDevelopmentCard));
+}
+
+#[test]
+fn test_flat_robber_without_victim() {
+    let mut game = fixed_game(&[&[], &[], &[], &[]], |state| {
+        state["phase"] = serde_json::json!("PreRoll");
+        state["players"][0]["development_cards"]["Knight"] = serde_json::json!(1);
+    });
+    let flat = |action_type, action_metadata| ActionMessage::Flat(FlatAction { action_type, action_metadata });
+    roll_until(&mut game, 0, 7);
+    let robber_moves = game.legal_actions(0);
+    let empty_tile = robber_moves.iter().find_map(|action| match action {
+        Action::MoveRobber { tile, victim: None } => Some(*tile),
+        _ => None
+    }).unwrap();
+
+    // Older clients name themselves as the victim to steal from nobody, while any other player is still stolen from.
+    assert!(flat(ActionType::PlaceRobber, [empty_tile, 2, 0, 0, 0, 0, 0, 0, 0, 0]).toActions(&game, 0) == Ok(vec![Action::MoveRobber { tile: empty_tile, victim: Some(2) }]));
+    assert!(flat(ActionType::PlayDevelopmentCard, [0, empty_tile, 0, 0, 0, 0, 0, 0, 0, 0]).toActions(&game, 0) == Ok(vec![Action::PlayKnight { tile: empty_tile, victim: None }]));
+    let actions = flat(ActionType::PlaceRobber, [empty_tile, 0, 0, 0, 0, 0, 0, 0, 0, 0]).toActions(&game, 0).unwrap();
+    assert!(actions == vec![Action::MoveRobber { tile: empty_tile, victim: None }]);
+    game.takeActions(actions, 0).ok().unwrap();
+    assert_eq!(game.view(None).board.robber, empty_tile);
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+}
+
+#[test]
+fn test_flat_road_building() {
+    let mut game = fixed_game(&[&[], &[], &[], &[]], |state| {
+        state["players"][0]["development_cards"]["RoadBuilding"] = serde_json::json!(1);
+    });
+    let mut roads = vec![];
+    let mut trial = game.clone();
+    trial.takeAction(Action::PlayRoadBuilding, 0).ok().unwrap();
+    for _ in 0..2 {
+        let road = trial.legal_actions(0)[0].clone();
+        trial.takeAction(road.clone(), 0).ok().unwrap();
+        let Action::BuildRoad { edge } = road else { panic!("Expected a road.") };
+        roads.push(edge);
+    }
+    let flat_road_building = |edges: [usize; 2]| {
+        ActionMessage::Flat(FlatAction { action_type: ActionType::PlayDevelopmentCard, action_metadata: [2, edges[0], edges[1], 0, 0, 0, 0, 0, 0, 0] })
+    };
+
+    // If either road can't be placed, the card isn't played either.
+    let before = serde_json::to_value(game.view(None)).unwrap();
+    let actions = flat_road_building([roads[0], roads[0]]).toActions(&game, 0).ok().unwrap();
+    assert_eq!(game.takeActions(actions, 0).err(), Some(ActionError::PositionOccupied));
+    assert_eq!(serde_json::to_value(game.view(None)).unwrap(), before);
+
+    let actions = flat_road_building([roads[0], roads[1]]).toActions(&game, 0).ok().unwrap();
+    let delta = game.takeActions(actions, 0).ok().unwrap();
+    assert_eq!(delta.players[0].roads_placed, roads);
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+    assert_eq!(game.view(None).players[0].development_cards.as_ref().unwrap()[&DevelopmentCard::RoadBuilding], 0);
+}
+
+#[test]
+fn test_player_view_hides_hidden_information() {
+    let game = Game::new(ChitLayout::Balanced, Some(9));
+
+    let view = serde_json::to_value(game.view(Some(1))).unwrap();
+    assert!(view["players"][1]["resource_cards"].is_object());
+    assert!(view["players"][1]["development_cards"].is_object());
+    assert!(view["players"][0]["resource_cards"].is_null());
+    assert!(view["players"][0]["development_cards"].is_null());
+    assert!(view["players"][0]["victory_point_cards"].is_null());
+    assert_eq!(view["players"][1]["victory_point_cards"], 0);
+    assert_eq!(view["players"][0]["num_resource_cards"], 0);
+
+    // The deck order, and the seed it could be worked out from, are never shown.
+    assert_eq!(view["bank"]["num_development_cards"], 25);
+    assert!(view["bank"].get("development_cards").is_none());
+    assert!(view.get("seed").is_none());
+
+    // Without a seat, every hand is shown.
+    let view = serde_json::to_value(game.view(None)).unwrap();
+    assert!(view["players"].as_array().unwrap().iter().all(|player| player["resource_cards"].is_object()));
+}
+
+#[test]
+fn test_cloned_game_is_independent() {
+    let mut game = Game::new(ChitLayout::Balanced, Some(6));
+    let before = serde_json::to_value(game.view(None)).unwrap();
+
+    let mut clone = game.clone();
+    clone.takeAction(Action::BuildSettlement { node: 0 }, 0).ok().unwrap();
+    clone.takeAction(Action::BuildRoad { edge: 0 }, 0).ok().unwrap();
+    assert_eq!(serde_json::to_value(game.view(None)).unwrap(), before);
+
+    game.takeAction(Action::BuildSettlement { node: 53 }, 0).ok().unwrap();
+    assert!(clone.view(None).board.nodes[53].building.is_none());
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut game = Game::new(ChitLayout::Balanced, Some(7));
+    let play = |game: &mut Game| -> Vec<serde_json::Value> {
+        (0..100).map(|_| {
+            let player_id = game.current_player_id;
+            let legal_actions = game.legal_actions(player_id);
+            let action = legal_actions[legal_actions.len() / 3].clone();
+            serde_json::to_value(game.takeAction(action, player_id).ok().unwrap()).unwrap()
+        }).collect()
+    };
+    play(&mut game);
+
+    let snapshot = game.snapshot();
+    let before = serde_json::to_value(game.view(None)).unwrap();
+    let first_deltas = play(&mut game);
+    assert_ne!(serde_json::to_value(game.view(None)).unwrap(), before);
+
+    // Restoring gives back the same state, and the same dice rolls and card draws afterwards.
+    game.restore(&snapshot);
+    assert_eq!(serde_json::to_value(game.view(None)).unwrap(), before);
+    assert_eq!(play(&mut game), first_deltas);
+
+    let mut branch = Game::from(snapshot.clone());
+    assert_eq!(serde_json::to_value(branch.view(None)).unwrap(), before);
+    assert_eq!(play(&mut branch), first_deltas);
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut game = Game::new(ChitLayout::Balanced, Some(8));
+    assert!(!game.undo());
+
+    // Record the whole state, including the bank and every hand, after each action.
+    let mut states = vec![serde_json::to_value(game.view(None)).unwrap()];
+    while states.len() <= 150 && !game.isGameOver() {
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        game.takeAction(legal_actions[legal_actions.len() / 2].clone(), player_id).ok().unwrap();
+        states.push(serde_json::to_value(game.view(None)).unwrap());
+    }
+
+    // A rejected action changes nothing and can't be undone.
+    assert!(game.takeAction(Action::BuildCity { node: 54 }, game.current_player_id).is_err());
+    assert_eq!(&serde_json::to_value(game.view(None)).unwrap(), states.last().unwrap());
+
+    // Only the most recent actions can be undone.
+    let oldest_undoable = states.len() - 1 - MAX_UNDO_ACTIONS;
+    for state in states.iter().rev().skip(1).take(MAX_UNDO_ACTIONS) {
+        assert!(game.undo());
+        assert_eq!(&serde_json::to_value(game.view(None)).unwrap(), state);
+    }
+    assert!(!game.undo());
+    for state in states.iter().skip(oldest_undoable + 1) {
+        assert!(game.redo());
+        assert_eq!(&serde_json::to_value(game.view(None)).unwrap(), state);
+    }
+    assert!(!game.redo());
+
+    // Taking a new action after undoing drops the undone actions.
+    game.undo();
+    let player_id = game.current_player_id;
+    let legal_actions = game.legal_actions(player_id);
+    game.takeAction(legal_actions[0].clone(), player_id).ok().unwrap();
+    assert!(!game.canRedo());
+
+    // The undone and redone actions are part of the event log, and replay the same way.
+    let replayed = Game::replay(&game.eventLog()).ok().unwrap();
+    assert_eq!(serde_json::to_value(replayed.view(None)).unwrap(), serde_json::to_value(game.view(None)).unwrap());
+
+    // A game can be played without keeping any history.
+    game.setHistoryEnabled(false);
+    let player_id = game.current_player_id;
+    let legal_actions = game.legal_actions(player_id);
+    game.takeAction(legal_actions[0].clone(), player_id).ok().unwrap();
+    assert!(!game.canUndo());
+    assert!(!game.clone().canUndo());
+
+    // Without the history, a game plays out exactly the same as with it.
+    let mut with_history = Game::new(ChitLayout::Balanced, Some(5));
+    let mut without_history = Game::new(ChitLayout::Balanced, Some(5));
+    without_history.setHistoryEnabled(false);
+    for _ in 0..300 {
+        let player_id = with_history.current_player_id;
+        let legal_actions = with_history.legal_actions(player_id);
+        let action = legal_actions[legal_actions.len() / 2].clone();
+        assert!(with_history.takeAction(action.clone(), player_id).is_ok());
+        assert!(without_history.takeAction(action, player_id).is_ok());
+    }
+    assert_eq!(serde_json::to_value(without_history.view(None)).unwrap(), serde_json::to_value(with_history.view(None)).unwrap());
+}
+
+#[test]
+fn test_player_undo() {
+    let hand: &[(ResourceCard, usize)] = &[(ResourceCard::Brick, 2), (ResourceCard::Lumber, 2), (ResourceCard::Ore, 1), (ResourceCard::Wheat, 1), (ResourceCard::Sheep, 1)];
+    let mut game = fixed_game(&[hand, &[], &[], &[]], |_| {});
+    let road = game.legal_actions(0).into_iter().find(|action| matches!(action, Action::BuildRoad { .. })).unwrap();
+
+    // A player can take back their own action while it hasn't shown anything hidden.
+    game.takeAction(road, 0).ok().unwrap();
+    assert!(game.canPlayerUndo(0));
+    assert!(!game.canPlayerUndo(1));
+    assert!(game.undo());
+    assert!(game.canPlayerRedo(0));
+    assert!(!game.canPlayerRedo(1));
+    assert!(game.redo());
+
+    // Drawing a development card shows it, so only someone in control of every seat can take it back.
+    let deck_size = game.view(None).bank.num_development_cards;
+    game.takeAction(Action::BuyDevelopmentCard, 0).ok().unwrap();
+    let drawn = game.view(None).players[0].development_cards_drawn_this_turn.clone().unwrap();
+    assert!(!game.canPlayerUndo(0));
+    assert!(game.canUndo());
+
+    // The cards left are shuffled again when the draw is undone, so the card drawn can't be known in advance.
+    let mut shuffled = false;
+    for _ in 0..40 {
+        game.undo();
+        assert_eq!(game.view(None).bank.num_development_cards, deck_size);
+        game.takeAction(Action::BuyDevelopmentCard, 0).ok().unwrap();
+        shuffled |= game.view(None).players[0].development_cards_drawn_this_turn.clone().unwrap() != drawn;
+    }
+    assert!(shuffled);
+
+    // Undoing a dice roll doesn't wind back the dice either.
+    game.takeAction(Action::EndTurn, 0).ok().unwrap();
+    let mut rolls = vec![];
+    for _ in 0..20 {
+        let delta = game.takeAction(Action::RollDice, 1).ok().unwrap();
+        assert!(!game.canPlayerUndo(1));
+        rolls.push(delta.dice_roll.unwrap());
+        game.undo();
+    }
+    assert!(rolls.iter().any(|roll| *roll != rolls[0]));
+}
+
+#[test]
+fn test_event_log_replay() {
+    let mut game = Game::new(ChitLayout::Spiral, Some(10));
+    let mut states = vec![];
+    while states.len() < 150 && !game.isGameOver() {
+        states.push(serde_json::to_value(game.view(None)).unwrap());
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        game.takeAction(legal_actions[legal_actions.len() / 2].clone(), player_id).ok().unwrap();
+    }
+    states.push(serde_json::to_value(game.view(None)).unwrap());
+
+    let log = game.eventLog();
+    assert_eq!(log.numActions(), states.len() - 1);
+    assert!(log.events.iter().any(|event| matches!(event, Event::DiceRolled { .. })));
+
+    // The log survives being written out and read back.
+    let json_lines = log.toJsonLines();
+    assert_eq!(json_lines.lines().count(), log.events.len() + 1);
+    let read_log = EventLog::fromJsonLines(&json_lines).ok().unwrap();
+    assert!(read_log == log);
+    let replayed = Game::replay(&read_log).ok().unwrap();
+    assert_eq!(&serde_json::to_value(replayed.view(None)).unwrap(), states.last().unwrap());
+
+    // Replaying part of the log gives the game as it was after that many actions.
+    let action_indices: Vec<usize> = log.events.iter().enumerate()
+        .filter(|(_, event)| matches!(event, Event::ActionTaken { .. }))
+        .map(|(index, _)| index)
+        .collect();
+    for num_actions in [0, 20, 75] {
+        let partial = Game::replay(&log.truncated(action_indices[num_actions])).ok().unwrap();
+        assert_eq!(serde_json::to_value(partial.view(None)).unwrap(), states[num_actions]);
+    }
+
+    // A log from another version or with a tampered outcome is rejected.
+    let old_version = json_lines.replacen("\"version\":2", "\"version\":1", 1);
+    assert_eq!(EventLog::fromJsonLines(&old_version).err(), Some(ReplayError::UnsupportedVersion(1)));
+    let mut tampered = log.clone();
+    let dice_index = tampered.events.iter().position(|event| matches!(event, Event::DiceRolled { .. })).unwrap();
+    if let Event::DiceRolled { dice, .. } = &mut tampered.events[dice_index] {
+        dice[0] = dice[0] % 6 + 1;
+    }
+    assert_eq!(Game::replay(&tampered).err(), Some(ReplayError::OutcomeMismatch { index: dice_index }));
+}
+
+#[test]
+fn test_game_record_round_trip() {
+    let mut game = Game::new(ChitLayout::Random, Some(10));
+    for _ in 0..100 {
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        game.takeAction(legal_actions[legal_actions.len() / 2].clone(), player_id).ok().unwrap();
+    }
+    let state = serde_json::to_value(game.view(None)).unwrap();
+
+    let json = game.toRecord().toJson();
+    let record = GameRecord::fromJson(&json).ok().unwrap();
+    assert_eq!(record.events.len(), game.eventLog().events.len());
+    let imported = Game::fromRecord(&record).ok().unwrap();
+    assert_eq!(serde_json::to_value(imported.view(None)).unwrap(), state);
+
+    // The record plays back from the outcomes it lists rather than a seed, and older seeded records are rejected.
+    assert!(!json.contains("seed"));
+    let old_record = GameRecord::fromJson(&json.replacen("\"version\":3", "\"version\":2", 1)).ok().unwrap();
+    assert_eq!(Game::fromRecord(&old_record).err(), Some(ReplayError::UnsupportedVersion(2)));
+    let mut tampered = record.clone();
+    let steal_index = tampered.events.iter().position(|event| matches!(event, Event::CardStolen { .. })).unwrap();
+    if let Event::CardStolen { victim, thief, .. } = &mut tampered.events[steal_index] {
+        *victim = *thief;
+    }
+    assert_eq!(Game::fromRecord(&tampered).err(), Some(ReplayError::OutcomeMismatch { index: steal_index }));
+
+    // A game set up on an imported board can be replayed from its own event log.
+    let board = Board::fromBoardString(&record.board).unwrap();
+    let mut game = Game::withBoard(board, 4, Some(11)).unwrap();
+    game.takeAction(Action::BuildSettlement { node: 10 }, 0).ok().unwrap();
+    let replayed = Game::replay(&game.eventLog()).ok().unwrap();
+    assert_eq!(serde_json::to_value(replayed.view(None)).unwrap(), serde_json::to_value(game.view(None)).unwrap());
+
+    let mut bad_record = record.clone();
+    bad_record.board = String::from("not a board");
+    assert!(matches!(Game::fromRecord(&bad_record).err(), Some(ReplayError::InvalidSetup(_))));
+}
+
+// A finished four player game in Catanatron's game JSON, with its tiles and node ids in Catanatron's order.
+const CATANATRON_GAME: &str = include_str!("data/catanatron_game.json");
+
+#[test]
+fn test_catanatron_game_round_trip() {
+    let game = Game::fromCatanatronJson(CATANATRON_GAME).ok().unwrap();
+    assert!(game.isGameOver());
+    assert_eq!(game.winner, Some(2));
+
+    // Writing the game out and reading it back gives the same game, with the same actions apart from the node ids.
+    let json = game.toCatanatronJson().unwrap();
+    let read_game = Game::fromCatanatronJson(&json).ok().unwrap();
+    assert_eq!(serde_json::to_value(read_game.view(None)).unwrap(), serde_json::to_value(game.view(None)).unwrap());
+    let sample: serde_json::Value = serde_json::from_str(CATANATRON_GAME).unwrap();
+    let written: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let without_nodes = |game: &serde_json::Value| -> Vec<serde_json::Value> {
+        game["actions"].as_array().unwrap().iter().map(|action| match action[1].as_str().unwrap() {
+            "BUILD_SETTLEMENT" | "BUILD_CITY" | "BUILD_ROAD" => serde_json::json!([action[0], action[1]]),
+            _ => action.clone(),
+        }).collect()
+    };
+    assert_eq!(without_nodes(&written), without_nodes(&sample));
+    for key in ["colors", "robber_coordinate", "winning_color"] {
+        assert_eq!(written[key], sample[key]);
+    }
+
+    // Discards are listed in seat order and played starting with the player who rolled, so after the third player
+    // rolls a seven the sample's discards by the first and third players are played the other way around.
+    let mut discard_runs = vec![vec![]];
+    for event in game.eventLog().events {
+        match event {
+            Event::ActionTaken { player_id, action: Action::Discard { .. } } => discard_runs.last_mut().unwrap().push(player_id),
+            Event::ActionTaken { .. } => discard_runs.push(vec![]),
+            _ => {}
+        }
+    }
+    discard_runs.retain(|run| run.len() > 1);
+    assert_eq!(discard_runs, [vec![1, 2], vec![2, 0], vec![0, 2]]);
+
+    // Trades between players, undone actions and games that end differently can't be read or written.
+    let mut trade = sample.clone();
+    trade["actions"][40] = serde_json::json!(["RED", "OFFER_TRADE", [1, 0, 0, 0, 0, 0, 0, 0, 0, 1]]);
+    assert!(matches!(Game::fromCatanatronJson(&trade.to_string()).err(), Some(ReplayError::UnsupportedAction { index: 40, .. })));
+    let mut moved_robber = sample.clone();
+    moved_robber["robber_coordinate"] = serde_json::json!([0, 0, 0]);
+    assert!(matches!(Game::fromCatanatronJson(&moved_robber.to_string()).err(), Some(ReplayError::InvalidSetup(_))));
+    let mut undone = Game::new(ChitLayout::Balanced, Some(14));
+    undone.takeAction(undone.legal_actions(0)[0].clone(), 0).ok().unwrap();
+    undone.undo();
+    assert!(undone.toCatanatronJson().is_err());
+    assert!(Game::withPlayers(5, ChitLayout::Balanced, Some(14)).unwrap().toCatanatronJson().is_err());
+}
+
+#[test]
+fn test_player_counts() {
+    assert!(Game::withPlayers(1, ChitLayout::Balanced, Some(12)).is_err());
+    assert!(Game::withPlayers(7, ChitLayout::Balanced, Some(12)).is_err());
+
+    for num_players in 2..=6 {
+        let mut game = Game::withPlayers(num_players, ChitLayout::Balanced, Some(12)).unwrap();
+        assert_eq!(game.view(None).players.len(), num_players);
+        assert_eq!(game.view(None).board.tiles.len(), if num_players > 4 { 30 } else { 19 });
+        for _ in 0..200 {
+            if game.isGameOver() {
+                break;
+            }
+            let player_id = game.current_player_id;
+            assert!(player_id < num_players);
+            let legal_actions = game.legal_actions(player_id);
+            assert!(game.takeAction(legal_actions[legal_actions.len() / 2].clone(), player_id).is_ok());
+        }
+    }
+}
+
+#[test]
+fn test_special_building_phase() {
+    let mut game = Game::withPlayers(6, ChitLayout::Balanced, Some(13)).unwrap();
+    loop {
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        if legal_actions.contains(&Action::EndTurn) {
+            break;
+        }
+        game.takeAction(legal_actions[0].clone(), player_id).ok().unwrap();
+    }
+
+    // Once a turn ends, every other player in turn may build before the next turn starts.
+    let turn_player_id = game.current_player_id;
+    game.takeAction(Action::EndTurn, turn_player_id).ok().unwrap();
+    for i in 1..6 {
+        let player_id = (turn_player_id + i) % 6;
+        assert!(game.isSpecialBuildingPhase());
+        assert_eq!(game.current_player_id, player_id);
+        assert_eq!(game.takeAction(Action::RollDice, player_id).err(), Some(ActionError::WrongPhase));
+        assert!(game.legal_actions(player_id).iter().all(|action| matches!(action,
+            Action::BuildRoad { .. } | Action::BuildSettlement { .. } | Action::BuildCity { .. } | Action::BuyDevelopmentCard | Action::EndTurn)));
+        game.takeAction(Action::EndTurn, player_id).ok().unwrap();
+    }
+    assert!(!game.isSpecialBuildingPhase());
+    assert_eq!(game.current_player_id, (turn_player_id + 1) % 6);
+    assert!(game.takeAction(Action::RollDice, game.current_player_id).is_ok());
+}
+
+#[test]
+fn test_special_building_keeps_turn_state() {
+    let development_card_hand: &[(ResourceCard, usize)] = &[(ResourceCard::Ore, 1), (ResourceCard::Wheat, 1), (ResourceCard::Sheep, 1)];
+    let mut game = fixed_game(&[&[(ResourceCard::Ore, 1)], development_card_hand, &[], &[], &[], &[]], |state| {
+        let pointer = state["bank"]["development_card_pointer"].as_u64().unwrap() as usize;
+        state["bank"]["development_cards"][pointer] = serde_json::json!("Knight");
+    });
+    let offer = Action::OfferTrade { giving: HashMap::from([(ResourceCard::Ore, 1)]), receiving: HashMap::from([(ResourceCard::Wheat, 1)]) };
+    game.takeAction(offer, 0).ok().unwrap();
+    game.takeAction(Action::DeclineTrade { offer_id: game.openTradeOfferIds()[0] }, 0).ok().unwrap();
+
+    // Players passing in the special building phase don't end a turn of their own.
+    game.takeAction(Action::EndTurn, 0).ok().unwrap();
+    game.takeAction(Action::BuyDevelopmentCard, 1).ok().unwrap();
+    for player_id in 1..6 {
+        assert!(game.isSpecialBuildingPhase());
+        game.takeAction(Action::EndTurn, player_id).ok().unwrap();
+    }
+    assert_eq!(game.pastTradeOffers().len(), 1);
+    assert_eq!(game.pastTradeOffers()[0].len(), 1);
+
+    // A card bought in the special building phase can be played from the next turn.
+    let knight = game.legal_actions(1).into_iter().find(|action| matches!(action, Action::PlayKnight { .. }));
+    assert!(knight.is_some());
+
+    // Every real turn adds one entry to the trade offer history, however many players build after it.
+    for turn in 1..4 {
+        let turn_player_id = game.current_player_id;
+        roll_until(&mut game, turn_player_id, 8);
+        for i in 0..6 {
+            game.takeAction(Action::EndTurn, (turn_player_id + i) % 6).ok().unwrap();
+        }
+        assert_eq!(game.pastTradeOffers().len(), turn + 1);
+    }
+}
+
+#[test]
+fn test_win_only_on_own_turn() {
+    let hand: &[(ResourceCard, usize)] = &[(ResourceCard::Ore, 1), (ResourceCard::Wheat, 1), (ResourceCard::Sheep, 1)];
+    let mut game = fixed_game(&[&[], hand, &[], &[], &[], &[]], |state| {
+        state["players"][1]["victory_points"] = serde_json::json!(9);
+        let pointer = state["bank"]["development_card_pointer"].as_u64().unwrap() as usize;
+        state["bank"]["development_cards"][pointer] = serde_json::json!("VictoryPoint");
+    });
+
+    // Reaching ten points while building on another player's turn doesn't win yet.
+    game.takeAction(Action::EndTurn, 0).ok().unwrap();
+    assert!(game.isSpecialBuildingPhase());
+    game.takeAction(Action::BuyDevelopmentCard, 1).ok().unwrap();
+    assert!(!game.isGameOver());
+    for player_id in 1..6 {
+        game.takeAction(Action::EndTurn, player_id).ok().unwrap();
+    }
+
+    // The player wins as soon as their own turn starts.
+    assert!(game.isGameOver());
+    assert_eq!(game.winner, Some(1));
+}
+
+#[test]
+fn test_victory_detection() {
+    let hand: &[(ResourceCard, usize)] = &[(ResourceCard::Ore, 4), (ResourceCard::Wheat, 3), (ResourceCard::Sheep, 1)];
+    let mut game = fixed_game(&[hand, &[], &[], &[]], |state| {
+        state["players"][0]["victory_points"] = serde_json::json!(8);
+        state["players"][2]["victory_points"] = serde_json::json!(6);
+        let pointer = state["bank"]["development_card_pointer"].as_u64().unwrap() as usize;
+        state["bank"]["development_cards"][pointer] = serde_json::json!("VictoryPoint");
+    });
+
+    // Nine points isn't enough.
+    let node = game.view(None).players[0].settlement_placements[0];
+    game.takeAction(Action::BuildCity { node }, 0).ok().unwrap();
+    assert!(!game.isGameOver());
+    assert_eq!(game.view(Some(1)).players[0].victory_points, 9);
+
+    // A victory point card makes ten, which wins straight away and reveals the card.
+    game.takeAction(Action::BuyDevelopmentCard, 0).ok().unwrap();
+    assert!(game.isGameOver());
+    assert_eq!(game.phase(), GamePhase::GameOver);
+    assert_eq!(game.winner, Some(0));
+    assert!(game.eventLog().events.last() == Some(&Event::VictoryPointCardsRevealed { player_id: 0, amount: 1 }));
+    let view = game.view(Some(1));
+    assert!(view.game_ended);
+    assert_eq!(view.winner, Some(0));
+    assert_eq!((view.players[0].victory_points, view.players[0].victory_point_cards), (10, Some(1)));
+    let standings: Vec<(usize, usize)> = game.final_standings.iter().map(|standing| (standing.player_id, standing.victory_points)).collect();
+    assert_eq!(standings, vec![(0, 10), (2, 6), (1, 2), (3, 2)]);
+
+    // Nothing else can be done once the game is over.
+    assert_eq!(game.takeAction(Action::EndTurn, 0).err(), Some(ActionError::GameOver));
+    assert_eq!(game.takeAction(Action::RollDice, 1).err(), Some(ActionError::GameOver));
+    assert!(game.legal_actions(0).is_empty());
+}
+
+#[test]
+fn test_turn_phases() {
+    let mut game = Game::new(ChitLayout::Balanced, Some(3));
+    while game.isSetupPhase() {
+        let player_id = game.current_player_id;
+        game.takeAction(game.legal_actions(player_id)[0].clone(), player_id).ok().unwrap();
+    }
+    assert_eq!(game.phase(), GamePhase::PreRoll);
+    assert_eq!(serde_json::to_value(game.view(None)).unwrap()["phase"], "PreRoll");
+    assert_eq!(game.takeAction(Action::EndTurn, 0).err(), Some(ActionError::DiceNotRolled));
+
+    // Each phase only allows the actions it is waiting for.
+    for _ in 0..300 {
+        if game.isGameOver() {
+            break;
+        }
+        let phase = game.phase();
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        assert!(legal_actions.iter().all(|action| match phase {
+            GamePhase::PreRoll => matches!(action, Action::RollDice | Action::PlayKnight { .. }),
+            GamePhase::MustDiscard => matches!(action, Action::Discard { .. }),
+            GamePhase::MoveRobber => matches!(action, Action::MoveRobber { .. }),
+            GamePhase::TradeNegotiation => matches!(action, Action::AcceptTrade { .. } | Action::DeclineTrade { .. }),
+            GamePhase::RoadBuildingPending { .. } => matches!(action, Action::BuildRoad { .. }),
+            GamePhase::MainPhase => !matches!(action, Action::RollDice | Action::Discard { .. } | Action::MoveRobber { .. }),
+            _ => false,
+        }));
+
+        let action = legal_actions[legal_actions.len() / 2].clone();
+        game.takeAction(action.clone(), player_id).ok().unwrap();
+        match action {
+            Action::RollDice => assert!(matches!(game.phase(), GamePhase::MainPhase | GamePhase::MustDiscard | GamePhase::MoveRobber)),
+            Action::EndTurn if !game.isGameOver() => {
+                assert_eq!(game.phase(), GamePhase::PreRoll);
+                assert_eq!(game.current_player_id, (player_id + 1) % 4);
+            },
+            Action::PlayRoadBuilding => assert!(matches!(game.phase(), GamePhase::RoadBuildingPending { .. } | GamePhase::MainPhase)),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_seven_roll_discards_and_robber() {
+    let mut game = fixed_game(&[
+        &[(ResourceCard::Ore, 5), (ResourceCard::Wheat, 4)],
+        &[(ResourceCard::Sheep, 7)],
+        &[(ResourceCard::Brick, 4), (ResourceCard::Lumber, 4)],
+        &[(ResourceCard::Wheat, 3)],
+    ], |state| state["phase"] = serde_json::json!("PreRoll"));
+    let hand = |game: &Game, player_id: usize| game.view(None).players[player_id].resource_cards.clone().unwrap();
+
+    // Only the players with more than seven cards discard, starting from the player who rolled.
+    let delta = roll_until(&mut game, 0, 7);
+    assert!(delta.players.iter().all(|player_delta| player_delta.resource_changes.values().all(|amount| *amount == 0)));
+    assert_eq!(game.view(None).players_to_discard, vec![0, 2]);
+
+    // Each player discards exactly half of their hand in turn, and nothing else can be done until then.
+    assert_eq!(game.phase(), GamePhase::MustDiscard);
+    assert_eq!(game.current_player_id, 0);
+    assert_eq!(game.takeAction(Action::EndTurn, 0).err(), Some(ActionError::WrongPhase));
+    assert_eq!(game.takeAction(Action::Discard { resources: HashMap::from([(ResourceCard::Brick, 4)]) }, 2).err(), Some(ActionError::NotYourTurn));
+    assert_eq!(game.takeAction(Action::Discard { resources: HashMap::from([(ResourceCard::Ore, 5)]) }, 0).err(), Some(ActionError::InvalidDiscard));
+    assert_eq!(game.takeAction(Action::Discard { resources: HashMap::from([(ResourceCard::Sheep, 4)]) }, 0).err(), Some(ActionError::InvalidDiscard));
+    game.takeAction(Action::Discard { resources: HashMap::from([(ResourceCard::Ore, 3), (ResourceCard::Wheat, 1)]) }, 0).ok().unwrap();
+    assert_eq!(hand(&game, 0)[&ResourceCard::Ore], 2);
+    assert_eq!(hand(&game, 0)[&ResourceCard::Wheat], 3);
+
+    assert_eq!(game.phase(), GamePhase::MustDiscard);
+    assert_eq!(game.current_player_id, 2);
+    assert_eq!(game.takeAction(Action::Discard { resources: HashMap::from([(ResourceCard::Sheep, 3)]) }, 1).err(), Some(ActionError::NotYourTurn));
+    game.takeAction(Action::Discard { resources: HashMap::from([(ResourceCard::Brick, 4)]) }, 2).ok().unwrap();
+    assert_eq!(hand(&game, 2)[&ResourceCard::Brick], 0);
+    assert_eq!(hand(&game, 2)[&ResourceCard::Lumber], 4);
+
+    // Then the player who rolled has to move the robber before anything else.
+    assert_eq!(game.phase(), GamePhase::MoveRobber);
+    assert_eq!(game.current_player_id, 0);
+    assert!(game.view(None).players_to_discard.is_empty());
+    assert_eq!(game.takeAction(Action::EndTurn, 0).err(), Some(ActionError::WrongPhase));
+    let robber_moves = game.legal_actions(0);
+    assert!(robber_moves.iter().all(|action| matches!(action, Action::MoveRobber { .. })));
+    let robber_tile = game.view(None).board.robber;
+    assert_eq!(game.takeAction(Action::MoveRobber { tile: robber_tile, victim: None }, 0).err(), Some(ActionError::InvalidRobberPlacement));
+
+    // Moving it next to another player steals one of their cards.
+    let robber_move = robber_moves.into_iter().find(|action| matches!(action, Action::MoveRobber { victim: Some(1), .. })).unwrap();
+    let Action::MoveRobber { tile, .. } = robber_move else { unreachable!() };
+    game.takeAction(robber_move, 0).ok().unwrap();
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+    assert_eq!(hand(&game, 1)[&ResourceCard::Sheep], 6);
+    assert_eq!(hand(&game, 0)[&ResourceCard::Sheep], 1);
+    assert_eq!(game.view(None).players[0].num_resource_cards, 6);
+
+    // There is only ever one robber.
+    let board = game.view(None).board;
+    assert_eq!(board.robber, tile);
+    assert_eq!(board.toBoardString().matches('*').count(), 1);
+    assert!(game.takeAction(Action::EndTurn, 0).is_ok());
+}
+
+#[test]
+fn test_robber_tile_produces_nothing() {
+    // Make a tile next to the first player's settlement the only one with a number, and roll it.
+    let view_game = fixed_game(&[&[], &[], &[], &[]], |_| {});
+    let board = view_game.view(None).board;
+    let node = view_game.view(None).players[0].settlement_placements[0];
+    let tile = *board.nodeTiles(node).iter().find(|tile| board.tiles[**tile].terrain != Terrain::Desert).unwrap();
+    let resource = board.tiles[tile].terrain.resource().unwrap();
+    let roll_tile = |robber_tile: usize| {
+        let mut game = fixed_game(&[&[], &[], &[], &[]], |state| {
+            state["phase"] = serde_json::json!("PreRoll");
+            state["board"]["robber"] = serde_json::json!(robber_tile);
+            for (index, state_tile) in state["board"]["tiles"].as_array_mut().unwrap().iter_mut().enumerate() {
+                state_tile["chit"] = serde_json::json!(if index == tile { 6 } else { 0 });
+            }
+        });
+        roll_until(&mut game, 0, 6)
+    };
+
+    // With the robber on the tile, nobody collects anything from it.
+    let delta = roll_tile(tile);
+    assert!(delta.players.iter().all(|player_delta| player_delta.resource_changes.values().all(|amount| *amount == 0)));
+
+    // Anywhere else, the settlement collects from it as usual.
+    let other_tile = (tile + 1) % board.tiles.len();
+    let delta = roll_tile(other_tile);
+    let player_delta = delta.players.iter().find(|player_delta| player_delta.player_id == 0).unwrap();
+    assert!(player_delta.resource_changes[&resource] >= 1);
+}
+
+#[test]
+fn test_maritime_trade_without_port() {
+    // On a board without ports, every resource trades with the bank four for one.
+    let mut game = fixed_game(&[&[(ResourceCard::Ore, 9)], &[], &[], &[]], |state| {
+        state["board"]["ports"] = serde_json::json!([]);
+        state["board"]["port_node_mapping"] = serde_json::json!([]);
+        state["bank"]["resource_cards"]["Brick"] = serde_json::json!(0);
+    });
+    let four_for_one: HashMap<ResourceCard, usize> = ResourceCard::ALL.into_iter().map(|resource| (resource, 4)).collect();
+    assert!(game.maritimeRates(0) == four_for_one);
+    let trade = |amount, receiving_resource| Action::MaritimeTrade {
+        giving: HashMap::from([(ResourceCard::Ore, amount)]),
+        receiving: HashMap::from([(receiving_resource, 1)]),
+    };
+    assert_eq!(game.takeAction(trade(3, ResourceCard::Wheat), 0).err(), Some(ActionError::InvalidTrade));
+    assert_eq!(game.takeAction(trade(5, ResourceCard::Wheat), 0).err(), Some(ActionError::InvalidTrade));
+    assert_eq!(game.takeAction(trade(4, ResourceCard::Ore), 0).err(), Some(ActionError::InvalidTrade));
+    assert_eq!(game.takeAction(trade(4, ResourceCard::Brick), 0).err(), Some(ActionError::InsufficientBankResources));
+
+    // Only the trades of four ore for a resource the bank has are legal.
+    let maritime_trades: Vec<Action> = game.legal_actions(0).into_iter().filter(|action| matches!(action, Action::MaritimeTrade { .. })).collect();
+    assert!(maritime_trades == vec![trade(4, ResourceCard::Wheat), trade(4, ResourceCard::Sheep), trade(4, ResourceCard::Lumber)]);
+
+    // Four ore go to the bank for one wheat.
+    let bank_before = game.view(None).bank.resource_cards;
+    let delta = game.takeAction(trade(4, ResourceCard::Wheat), 0).ok().unwrap();
+    assert_eq!(delta.players[0].resource_changes[&ResourceCard::Ore], -4);
+    assert_eq!(delta.players[0].resource_changes[&ResourceCard::Wheat], 1);
+    let hand = game.view(None).players[0].resource_cards.clone().unwrap();
+    assert_eq!((hand[&ResourceCard::Ore], hand[&ResourceCard::Wheat]), (5, 1));
+    let bank_after = game.view(None).bank.resource_cards;
+    assert_eq!(bank_after[&ResourceCard::Ore], bank_before[&ResourceCard::Ore] + 4);
+    assert_eq!(bank_after[&ResourceCard::Wheat], bank_before[&ResourceCard::Wheat] - 1);
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+}
+
+#[test]
+fn test_combined_maritime_trade() {
+    // Put a 3:1 port and a 2:1 sheep port on the first player's settlements.
+    let hands: &[&[(ResourceCard, usize)]] = &[&[(ResourceCard::Sheep, 4), (ResourceCard::Ore, 3), (ResourceCard::Brick, 2)], &[], &[], &[]];
+    let view_game = fixed_game(hands, |_| {});
+    let board = view_game.view(None).board;
+    let port_nodes: Vec<(usize, usize)> = view_game.view(None).players[0].settlement_placements.iter().map(|node| {
+        let [node1, node2] = board.edgeNodes(board.nodeEdges(*node)[0]);
+        (*node, if node1 == *node { node2 } else { node1 })
+    }).collect();
+    let mut game = fixed_game(hands, |state| {
+        state["board"]["ports"] = serde_json::json!(["ThreeToOne", "Sheep"]);
+        state["board"]["port_node_mapping"] = serde_json::json!(port_nodes);
+    });
+    let maritime_rates = game.maritimeRates(0);
+    assert!(ResourceCard::ALL.into_iter().all(|resource| maritime_rates[&resource] == if resource == ResourceCard::Sheep { 2 } else { 3 }));
+
+    // Only single batches of one resource are listed, for each resource the player has enough of.
+    let maritime_trades = game.legal_actions(0).into_iter().filter(|action| matches!(action, Action::MaritimeTrade { .. })).count();
+    assert_eq!(maritime_trades, 8);
+
+    // Each batch has to be a whole number at the player's rate for its resource, and gets one card of a resource not given.
+    let trade = |giving: &[(ResourceCard, usize)], receiving: &[(ResourceCard, usize)]| Action::MaritimeTrade {
+        giving: giving.iter().copied().collect(),
+        receiving: receiving.iter().copied().collect(),
+    };
+    let invalid_trades = [
+        trade(&[(ResourceCard::Sheep, 3), (ResourceCard::Ore, 3)], &[(ResourceCard::Wheat, 2)]),
+        trade(&[(ResourceCard::Sheep, 4), (ResourceCard::Ore, 3)], &[(ResourceCard::Wheat, 2)]),
+        trade(&[(ResourceCard::Sheep, 4), (ResourceCard::Ore, 3)], &[(ResourceCard::Wheat, 4)]),
+        trade(&[(ResourceCard::Sheep, 4), (ResourceCard::Ore, 3)], &[(ResourceCard::Ore, 1), (ResourceCard::Wheat, 2)]),
+        trade(&[(ResourceCard::Brick, 2)], &[(ResourceCard::Wheat, 1)]),
+    ];
+    for invalid_trade in invalid_trades {
+        assert_eq!(game.takeAction(invalid_trade, 0).err(), Some(ActionError::InvalidTrade));
+    }
+    assert_eq!(game.takeAction(trade(&[(ResourceCard::Sheep, 6)], &[(ResourceCard::Wheat, 3)]), 0).err(), Some(ActionError::InsufficientResources));
+
+    // Two sheep batches and an ore batch make three cards, which can be of different resources.
+    let bank_before = game.view(None).bank.resource_cards;
+    game.takeAction(trade(&[(ResourceCard::Sheep, 4), (ResourceCard::Ore, 3)], &[(ResourceCard::Wheat, 2), (ResourceCard::Lumber, 1)]), 0).ok().unwrap();
+    let hand = game.view(None).players[0].resource_cards.clone().unwrap();
+    let expected_hand = HashMap::from([
+        (ResourceCard::Ore, 0), (ResourceCard::Wheat, 2), (ResourceCard::Sheep, 0), (ResourceCard::Brick, 2), (ResourceCard::Lumber, 1),
+    ]);
+    assert!(hand == expected_hand);
+    let bank_after = game.view(None).bank.resource_cards;
+    let bank_changes = [(ResourceCard::Ore, 3), (ResourceCard::Wheat, -2), (ResourceCard::Sheep, 4), (ResourceCard::Brick, 0), (ResourceCard::Lumber, -1)];
+    for (resource, change) in bank_changes {
+        assert_eq!(bank_after[&resource] as i32, bank_before[&resource] as i32 + change);
+    }
+}
+
+#[test]
+fn test_offer_trade_goes_to_the_players() {
+    let mut game = fixed_game(&[&[(ResourceCard::Ore, 4)], &[(ResourceCard::Wheat, 1)], &[], &[]], |_| {});
+    let rate = game.maritimeRates(0)[&ResourceCard::Ore];
+    let giving = HashMap::from([(ResourceCard::Ore, rate)]);
+    let receiving = HashMap::from([(ResourceCard::Wheat, 1)]);
+    let mut metadata = [0; 10];
+    (metadata[0], metadata[6]) = (rate, 1);
+    let flat_offer = || ActionMessage::Flat(FlatAction { action_type: ActionType::OfferTrade, action_metadata: metadata });
+
+    // An offer the bank would take is still made to the other players.
+    game.takeAction(Action::OfferTrade { giving: giving.clone(), receiving: receiving.clone() }, 0).ok().unwrap();
+    assert_eq!(game.phase(), GamePhase::TradeNegotiation);
+    assert_eq!(game.view(None).players[0].resource_cards.as_ref().unwrap()[&ResourceCard::Ore], 4);
+
+    // Older clients trade with the bank with a flat offer, which is only a maritime trade when one can be made.
+    assert!(flat_offer().toActions(&game, 0) == Ok(vec![Action::OfferTrade { giving: giving.clone(), receiving: receiving.clone() }]));
+    game.takeAction(Action::DeclineTrade { offer_id: game.openTradeOfferIds()[0] }, 0).ok().unwrap();
+    assert!(flat_offer().toActions(&game, 0) == Ok(vec![Action::MaritimeTrade { giving, receiving }]));
+}
+
+#[test]
+fn test_flat_trade_responses() {
+    let mut game = fixed_game(&[&[(ResourceCard::Ore, 2)], &[(ResourceCard::Wheat, 1)], &[], &[]], |_| {});
+    let offer = |amount| Action::OfferTrade { giving: HashMap::from([(ResourceCard::Ore, amount)]), receiving: HashMap::from([(ResourceCard::Wheat, 1)]) };
+    let flat = |action_type, player| ActionMessage::Flat(FlatAction { action_type, action_metadata: [player, 0, 0, 0, 0, 0, 0, 0, 0, 0] });
+    assert_eq!(flat(ActionType::DeclineTrade, 0).toActions(&game, 1).err(), Some(ActionError::NoTradeOffer));
+
+    // Older clients can't give the offer they respond to, which is filled in while only one offer is open.
+    game.takeAction(offer(1), 0).ok().unwrap();
+    let offer_id = game.openTradeOfferIds()[0];
+    assert!(flat(ActionType::AcceptTrade, 0).toActions(&game, 1) == Ok(vec![Action::AcceptTrade { offer_id, player: 0 }]));
+    assert!(flat(ActionType::DeclineTrade, 0).toActions(&game, 1) == Ok(vec![Action::DeclineTrade { offer_id }]));
+
+    game.takeAction(offer(2), 0).ok().unwrap();
+    assert_eq!(flat(ActionType::AcceptTrade, 0).toActions(&game, 1).err(), Some(ActionError::AmbiguousTradeOffer));
+    assert_eq!(flat(ActionType::DeclineTrade, 0).toActions(&game, 1).err(), Some(ActionError::AmbiguousTradeOffer));
+}
+
+#[test]
+fn test_domestic_trade_negotiation() {
+    let mut game = fixed_game(&[
+        &[(ResourceCard::Ore, 2), (ResourceCard::Sheep, 1)],
+        &[(ResourceCard::Wheat, 2)],
+        &[(ResourceCard::Wheat, 1)],
+        &[],
+    ], |_| {});
+    let cards = |resources: &[(ResourceCard, usize)]| -> HashMap<ResourceCard, usize> { resources.iter().copied().collect() };
+    let offer = |giving: &[(ResourceCard, usize)], receiving: &[(ResourceCard, usize)]| Action::OfferTrade { giving: cards(giving), receiving: cards(receiving) };
+    let hand = |game: &Game, player_id: usize| {
+        let hand = game.view(None).players[player_id].resource_cards.clone().unwrap();
+        hand.into_iter().filter(|(_, amount)| *amount > 0).collect::<HashMap<ResourceCard, usize>>()
+    };
+    let responses = |game: &Game, index: usize| game.tradeOffers()[index].responses.clone();
+    let latest_offer = |game: &Game| *game.openTradeOfferIds().last().unwrap();
+
+    assert_eq!(game.takeAction(offer(&[(ResourceCard::Ore, 1)], &[(ResourceCard::Ore, 1)]), 0).err(), Some(ActionError::InvalidTrade));
+    assert_eq!(game.takeAction(offer(&[(ResourceCard::Ore, 1)], &[]), 0).err(), Some(ActionError::InvalidTrade));
+    assert_eq!(game.takeAction(offer(&[(ResourceCard::Ore, 3)], &[(ResourceCard::Wheat, 1)]), 0).err(), Some(ActionError::InsufficientResources));
+    assert_eq!(game.takeAction(Action::AcceptTrade { offer_id: 0, player: 0 }, 1).err(), Some(ActionError::NotYourTurn));
+
+    // The offer goes to every other player at once, and they respond out of turn.
+    game.takeAction(offer(&[(ResourceCard::Ore, 1)], &[(ResourceCard::Wheat, 1)]), 0).ok().unwrap();
+    let offer_id = latest_offer(&game);
+    assert_eq!(game.phase(), GamePhase::TradeNegotiation);
+    assert_eq!(game.current_player_id, 0);
+    assert_eq!(game.tradeOffers()[0].status, TradeStatus::Open);
+    assert!(responses(&game, 0) == vec![TradeResponse::Pending; 4]);
+    assert_eq!(game.takeAction(Action::EndTurn, 0).err(), Some(ActionError::TradeOfferPending));
+    assert_eq!(game.takeAction(Action::DeclineTrade { offer_id: offer_id + 1 }, 3).err(), Some(ActionError::NoTradeOffer));
+
+    // A player can only accept with the cards asked for, and the offering player can only trade with a player who accepted.
+    assert_eq!(game.takeAction(Action::AcceptTrade { offer_id, player: 0 }, 3).err(), Some(ActionError::InsufficientResources));
+    game.takeAction(Action::DeclineTrade { offer_id }, 3).ok().unwrap();
+    game.takeAction(Action::AcceptTrade { offer_id, player: 0 }, 2).ok().unwrap();
+    assert!(responses(&game, 0) == vec![TradeResponse::Pending, TradeResponse::Pending, TradeResponse::Accepted, TradeResponse::Declined]);
+    assert_eq!(game.takeAction(Action::AcceptTrade { offer_id, player: 1 }, 0).err(), Some(ActionError::InvalidTrade));
+    assert_eq!(game.takeAction(Action::AcceptTrade { offer_id, player: 3 }, 0).err(), Some(ActionError::InvalidTrade));
+
+    // Responses can change until the trade is made.
+    game.takeAction(Action::AcceptTrade { offer_id, player: 0 }, 1).ok().unwrap();
+    game.takeAction(Action::DeclineTrade { offer_id }, 2).ok().unwrap();
+    assert!(responses(&game, 0) == vec![TradeResponse::Pending, TradeResponse::Accepted, TradeResponse::Declined, TradeResponse::Declined]);
+    assert_eq!(game.tradeOffers()[0].status, TradeStatus::Open);
+
+    // Confirming the trade exchanges both players' cards and closes the offer.
+    game.takeAction(Action::AcceptTrade { offer_id, player: 1 }, 0).ok().unwrap();
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+    assert!(game.openTradeOfferIds().is_empty());
+    assert_eq!(game.tradeOffers()[0].status, TradeStatus::Completed { player: 1 });
+    assert!(hand(&game, 0) == cards(&[(ResourceCard::Ore, 1), (ResourceCard::Sheep, 1), (ResourceCard::Wheat, 1)]));
+    assert!(hand(&game, 1) == cards(&[(ResourceCard::Ore, 1), (ResourceCard::Wheat, 1)]));
+    assert_eq!(game.takeAction(Action::AcceptTrade { offer_id, player: 0 }, 2).err(), Some(ActionError::NotYourTurn));
+
+    // The offer closes once everyone declines it.
+    game.takeAction(offer(&[(ResourceCard::Sheep, 1)], &[(ResourceCard::Wheat, 1)]), 0).ok().unwrap();
+    let offer_id = latest_offer(&game);
+    for player_id in 1..4 {
+        assert_eq!(game.phase(), GamePhase::TradeNegotiation);
+        game.takeAction(Action::DeclineTrade { offer_id }, player_id).ok().unwrap();
+    }
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+    assert_eq!(game.tradeOffers()[1].status, TradeStatus::Declined);
+
+    // A counter-offer from another player can be confirmed instead, on its own terms.
+    game.takeAction(offer(&[(ResourceCard::Ore, 1)], &[(ResourceCard::Wheat, 2)]), 0).ok().unwrap();
+    let offer_id = latest_offer(&game);
+    let counter = |player_giving: &[(ResourceCard, usize)], player_receiving: &[(ResourceCard, usize)]| {
+        Action::CounterTrade { offer_id, giving: cards(player_giving), receiving: cards(player_receiving) }
+    };
+    assert_eq!(game.takeAction(counter(&[(ResourceCard::Ore, 1)], &[(ResourceCard::Wheat, 1)]), 0).err(), Some(ActionError::InvalidTrade));
+    assert_eq!(game.takeAction(counter(&[(ResourceCard::Wheat, 1)], &[(ResourceCard::Sheep, 1)]), 3).err(), Some(ActionError::InsufficientResources));
+    game.takeAction(counter(&[(ResourceCard::Wheat, 1)], &[(ResourceCard::Sheep, 1)]), 2).ok().unwrap();
+    let countered = TradeResponse::Countered { giving: cards(&[(ResourceCard::Wheat, 1)]), receiving: cards(&[(ResourceCard::Sheep, 1)]) };
+    assert!(responses(&game, 2) == vec![TradeResponse::Pending, TradeResponse::Pending, countered, TradeResponse::Pending]);
+    game.takeAction(Action::AcceptTrade { offer_id, player: 2 }, 0).ok().unwrap();
+    assert_eq!(game.tradeOffers()[2].status, TradeStatus::Completed { player: 2 });
+    assert!(hand(&game, 0) == cards(&[(ResourceCard::Ore, 1), (ResourceCard::Wheat, 2)]));
+    assert!(hand(&game, 2) == cards(&[(ResourceCard::Sheep, 1)]));
+
+    // Several offers can be open at once, and closing one leaves the others open.
+    game.takeAction(offer(&[(ResourceCard::Ore, 1)], &[(ResourceCard::Sheep, 1)]), 0).ok().unwrap();
+    let first_offer_id = latest_offer(&game);
+    game.takeAction(offer(&[(ResourceCard::Wheat, 1)], &[(ResourceCard::Ore, 1)]), 0).ok().unwrap();
+    let second_offer_id = latest_offer(&game);
+    assert_eq!(game.openTradeOfferIds(), vec![first_offer_id, second_offer_id]);
+    game.takeAction(Action::DeclineTrade { offer_id: first_offer_id }, 0).ok().unwrap();
+    assert_eq!(game.phase(), GamePhase::TradeNegotiation);
+    assert_eq!(game.tradeOffers()[3].status, TradeStatus::Withdrawn);
+    assert_eq!(game.openTradeOfferIds(), vec![second_offer_id]);
+
+    // An offer that isn't settled in time is withdrawn, and the offers are only kept for the turn.
+    assert!(!game.expireTradeOffer(first_offer_id));
+    assert!(game.expireTradeOffer(second_offer_id));
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+    assert_eq!(game.tradeOffers()[4].status, TradeStatus::Withdrawn);
+    game.takeAction(Action::EndTurn, 0).ok().unwrap();
+    assert!(game.tradeOffers().is_empty());
+    let statuses: Vec<TradeStatus> = game.pastTradeOffers().last().unwrap().iter().map(|trade_offer| trade_offer.status).collect();
+    assert_eq!(statuses, vec![
+        TradeStatus::Completed { player: 1 }, TradeStatus::Declined, TradeStatus::Completed { player: 2 }, TradeStatus::Withdrawn, TradeStatus::Withdrawn,
+    ]);
+}
+
+#[test]
+fn test_trade_offer_expiry() {
+    let mut game = fixed_game(&[&[(ResourceCard::Ore, 2)], &[(ResourceCard::Wheat, 1)], &[], &[]], |_| {});
+    let offer = |amount| Action::OfferTrade { giving: HashMap::from([(ResourceCard::Ore, amount)]), receiving: HashMap::from([(ResourceCard::Wheat, 1)]) };
+    game.takeAction(offer(1), 0).ok().unwrap();
+    game.takeAction(offer(2), 0).ok().unwrap();
+    let [first_offer_id, second_offer_id] = game.openTradeOfferIds()[..] else { panic!("Expected two open offers.") };
+
+    // An offer that expires is withdrawn without being added to the history, so undone actions can still be redone.
+    game.undo();
+    assert!(game.expireTradeOffer(first_offer_id));
+    assert!(!game.expireTradeOffer(second_offer_id));
+    assert_eq!(game.tradeOffers()[0].status, TradeStatus::Withdrawn);
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+    assert!(game.canRedo());
+    assert!(game.canPlayerUndo(0));
+
+    // Offers made after undoing get new ids, so a timeout for an undone offer can't withdraw them.
+    game.takeAction(offer(2), 0).ok().unwrap();
+    assert!(game.openTradeOfferIds() == vec![second_offer_id + 1]);
+    assert!(!game.expireTradeOffer(second_offer_id));
+
+    assert!(game.eventLog().events.contains(&Event::TradeOfferExpired { offer_id: first_offer_id }));
+
+    // A game played from its seed replays to the same offers, expired ones included.
+    let mut game = Game::new(ChitLayout::Balanced, Some(100));
+    while game.isSetupPhase() {
+        let player_id = game.current_player_id;
+        game.takeAction(game.legal_actions(player_id)[0].clone(), player_id).ok().unwrap();
+    }
+    roll_until(&mut game, 0, 8);
+    let hand = game.view(None).players[0].resource_cards.clone().unwrap();
+    let giving_resource = ResourceCard::ALL.into_iter().find(|resource| hand[resource] > 0).unwrap();
+    let receiving_resource = ResourceCard::ALL.into_iter().find(|resource| *resource != giving_resource).unwrap();
+    game.takeAction(Action::OfferTrade { giving: HashMap::from([(giving_resource, 1)]), receiving: HashMap::from([(receiving_resource, 1)]) }, 0).ok().unwrap();
+    assert!(game.expireTradeOffer(game.openTradeOfferIds()[0]));
+    let replayed = Game::replay(&game.eventLog()).ok().unwrap();
+    assert_eq!(serde_json::to_value(replayed.view(None)).unwrap(), serde_json::to_value(game.view(None)).unwrap());
+    assert_eq!(replayed.tradeOffers()[0].status, TradeStatus::Withdrawn);
+}
+
+#[test]
+fn test_development_card_timing() {
+    let mut game = Game::new(ChitLayout::Balanced, Some(3));
+    let is_play = |action: &Action| matches!(action, Action::PlayKnight { .. } | Action::PlayMonopoly { .. } | Action::PlayRoadBuilding | Action::PlayYearOfPlenty { .. });
+    let card_played = |action: &Action| match action {
+        Action::PlayKnight { .. } => DevelopmentCard::Knight,
+        Action::PlayMonopoly { .. } => DevelopmentCard::Monopoly,
+        Action::PlayRoadBuilding => DevelopmentCard::RoadBuilding,
+        _ => DevelopmentCard::YearOfPlenty,
+    };
+    let development_cards = |game: &Game, player_id: usize| {
+        let player = &game.view(None).players[player_id];
+        (player.development_cards.clone().unwrap(), player.development_cards_drawn_this_turn.clone().unwrap())
+    };
+    let num_playable = |cards: &HashMap<DevelopmentCard, usize>| cards.iter().filter(|(card, _)| **card != DevelopmentCard::VictoryPoint).map(|(_, amount)| amount).sum::<usize>();
+
+    // Buy development cards whenever possible, and play them once there is another card that could be played after.
+    let (mut num_played, mut num_knights_before_roll, mut num_second_plays) = (0, 0, 0);
+    for _ in 0..3000 {
+        if game.isGameOver() || (num_played >= 3 && num_knights_before_roll >= 1 && num_second_plays >= 1) {
+            break;
+        }
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        let plays: Vec<Action> = legal_actions.iter().filter(|action| is_play(action)).cloned().collect();
+        let (cards_before, drawn_before) = development_cards(&game, player_id);
+
+        if legal_actions.contains(&Action::BuyDevelopmentCard) {
+            // A card bought this turn can't be played until the next turn.
+            game.takeAction(Action::BuyDevelopmentCard, player_id).ok().unwrap();
+            let (cards, drawn) = development_cards(&game, player_id);
+            assert!(cards == cards_before);
+            assert_eq!(drawn.values().sum::<usize>(), drawn_before.values().sum::<usize>() + 1);
+            assert!(game.legal_actions(player_id).iter().filter(|action| is_play(action)).all(|action| cards_before[&card_played(action)] > 0));
+        } else if let Some(play) = plays.first().filter(|_| num_playable(&cards_before) >= 2 || game.phase() == GamePhase::PreRoll) {
+            // Only one card can be played each turn.
+            if game.phase() == GamePhase::PreRoll {
+                num_knights_before_roll += 1;
+            }
+            game.takeAction(play.clone(), player_id).ok().unwrap();
+            num_played += 1;
+            let (cards, _) = development_cards(&game, player_id);
+            let other_plays = [
+                Action::PlayKnight { tile: 0, victim: None },
+                Action::PlayMonopoly { resource: ResourceCard::Brick },
+                Action::PlayRoadBuilding,
+                Action::PlayYearOfPlenty { first_resource: ResourceCard::Brick, second_resource: ResourceCard::Ore },
+            ];
+            for other_play in other_plays.iter().filter(|other_play| cards[&card_played(other_play)] > 0) {
+                if game.phase() == GamePhase::MainPhase || (game.phase() == GamePhase::PreRoll && card_played(other_play) == DevelopmentCard::Knight) {
+                    assert_eq!(game.checkAction(other_play, player_id).err(), Some(ActionError::DevelopmentCardAlreadyPlayed));
+                    num_second_plays += 1;
+                }
+            }
+            assert!(!game.legal_actions(player_id).iter().any(is_play));
+        } else if legal_actions.contains(&Action::EndTurn) {
+            // The cards drawn this turn can be played from the next turn.
+            game.takeAction(Action::EndTurn, player_id).ok().unwrap();
+            let (cards, drawn) = development_cards(&game, player_id);
+            assert!(drawn.values().all(|amount| *amount == 0));
+            assert!(cards.iter().all(|(card, amount)| *amount == cards_before[card] + drawn_before[card]));
+        } else {
+            game.takeAction(legal_actions[legal_actions.len() / 2].clone(), player_id).ok().unwrap();
+        }
+    }
+    assert!(num_played >= 3);
+    assert!(num_knights_before_roll >= 1);
+    assert!(num_second_plays >= 1);
+
+    // The winner's victory point cards are revealed as the game ends.
+    while !game.isGameOver() {
+        let player_id = game.current_player_id;
+        let legal_actions = game.legal_actions(player_id);
+        let action = legal_actions.iter().find(|action| matches!(action, Action::BuildCity { .. } | Action::BuildSettlement { .. } | Action::BuyDevelopmentCard))
+            .unwrap_or(&legal_actions[legal_actions.len() / 2]);
+        game.takeAction(action.clone(), player_id).ok().unwrap();
+    }
+    let winner = game.winner.unwrap();
+    let victory_point_cards = game.view(None).players[winner].victory_point_cards.unwrap();
+    assert!(victory_point_cards > 0);
+    assert!(game.eventLog().events.last() == Some(&Event::VictoryPointCardsRevealed { player_id: winner, amount: victory_point_cards }));
+    assert_eq!(serde_json::to_value(game.view(Some((winner + 1) % 4))).unwrap()["players"][winner]["victory_point_cards"], victory_point_cards);
+}
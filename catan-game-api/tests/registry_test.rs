@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::thread;
+use std::time::Duration;
+
+use catan_game_api::game::action::Action;
+use catan_game_api::game::chit_layout::ChitLayout;
+use catan_game_api::game::game::Game;
+use catan_game_api::game::phase::GamePhase;
+use catan_game_api::game::resource::ResourceCard;
+use catan_game_api::registry::{GameRegistry, RegistryError};
+
+#[test]
+fn test_create_join_and_close() {
+    let mut games = GameRegistry::new();
+    let (owner, other, third) = (games.newConnectionId(), games.newConnectionId(), games.newConnectionId());
+    assert_ne!(owner, other);
+
+    let game_id = games.addGame(Game::new(ChitLayout::Balanced, Some(1)), owner, None).unwrap();
+    assert_eq!(games.seat(game_id, owner), Ok(None));
+    assert_eq!(games.seat(game_id, other), Err(RegistryError::NotInGame));
+    assert_eq!(games.addGame(Game::new(ChitLayout::Balanced, Some(1)), owner, Some(4)).err(), Some(RegistryError::SeatUnavailable));
+
+    // A seat can only be played by one connection.
+    assert!(games.joinGame(game_id, other, 2).is_ok());
+    assert_eq!(games.seat(game_id, other), Ok(Some(2)));
+    assert_eq!(games.joinGame(game_id, third, 2).err(), Some(RegistryError::SeatUnavailable));
+    assert_eq!(games.joinGame(game_id, third, 4).err(), Some(RegistryError::SeatUnavailable));
+    assert_eq!(games.joinGame(game_id + 1, third, 1).err(), Some(RegistryError::GameNotFound));
+    assert!(games.joinGame(game_id, third, 1).is_ok());
+
+    // Only the connection that created the game can start it again, which unseats everyone else.
+    let old_game = games.getGame(game_id).unwrap();
+    assert_eq!(games.resetGame(game_id, Game::new(ChitLayout::Spiral, Some(2)), other, None), Err(RegistryError::NotOwner));
+    assert_eq!(games.resetGame(game_id, Game::new(ChitLayout::Spiral, Some(2)), owner, Some(0)), Ok(()));
+    assert!(!std::sync::Arc::ptr_eq(&old_game, &games.getGame(game_id).unwrap()));
+    assert_eq!(games.seat(game_id, owner), Ok(Some(0)));
+    assert_eq!(games.seat(game_id, other), Err(RegistryError::NotInGame));
+
+    // Only the connection that created the game can close it.
+    assert_eq!(games.removeGame(game_id, other), Err(RegistryError::NotOwner));
+    assert_eq!(games.removeGame(game_id, owner), Ok(()));
+    assert_eq!(games.removeGame(game_id, owner), Err(RegistryError::GameNotFound));
+    assert_eq!(games.numGames(), 0);
+}
+
+#[test]
+fn test_closed_connections_leave_their_games() {
+    let mut games = GameRegistry::new();
+    let (owner, other) = (games.newConnectionId(), games.newConnectionId());
+    let game_id = games.addGame(Game::new(ChitLayout::Balanced, Some(1)), owner, None).unwrap();
+    let other_game_id = games.addGame(Game::new(ChitLayout::Balanced, Some(1)), other, Some(0)).unwrap();
+    games.joinGame(game_id, other, 3).unwrap();
+
+    // The games a connection created are removed with it, and the seats it took in other games are freed.
+    assert_eq!(games.removeConnection(other), 1);
+    assert!(games.getGame(other_game_id).is_none());
+    assert_eq!(games.connections(game_id), vec![(owner, None)]);
+}
+
+#[test]
+fn test_abandoned_games_removed() {
+    let mut games = GameRegistry::new();
+    let owner = games.newConnectionId();
+    let abandoned_id = games.addGame(Game::new(ChitLayout::Balanced, Some(1)), owner, None).unwrap();
+    let active_id = games.addGame(Game::new(ChitLayout::Balanced, Some(1)), owner, None).unwrap();
+
+    // Only games that haven't been used within the idle time are removed.
+    thread::sleep(Duration::from_millis(50));
+    games.getGame(active_id);
+    assert_eq!(games.removeAbandonedGames(Duration::from_millis(25)), 1);
+    assert!(games.getGame(abandoned_id).is_none());
+    assert!(games.getGame(active_id).is_some());
+    assert_eq!(games.removeAbandonedGames(Duration::from_secs(60)), 0);
+    assert_eq!(games.numGames(), 1);
+}
+
+#[test]
+fn test_other_seats_see_trade_offers() {
+    let mut games = GameRegistry::new();
+    let (owner, other, third) = (games.newConnectionId(), games.newConnectionId(), games.newConnectionId());
+    let game_id = games.addGame(Game::new(ChitLayout::Balanced, Some(2)), owner, Some(0)).unwrap();
+    games.joinGame(game_id, other, 1).unwrap();
+    games.joinGame(game_id, third, 2).unwrap();
+
+    // Play setup and the first roll, which leaves the first player with cards to offer.
+    let shared_game = games.getGame(game_id).unwrap();
+    let mut game = shared_game.lock().unwrap();
+    while game.isSetupPhase() {
+        let player_id = game.current_player_id;
+        let action = game.legal_actions(player_id)[0].clone();
+        game.takeAction(action, player_id).ok().unwrap();
+    }
+    game.takeAction(Action::RollDice, 0).ok().unwrap();
+    assert_eq!(game.phase(), GamePhase::MainPhase);
+    let hand = game.view(Some(0)).players[0].resource_cards.clone().unwrap();
+    let giving_resource = ResourceCard::ALL.into_iter().find(|resource| hand[resource] > 0).unwrap();
+    let receiving_resource = ResourceCard::ALL.into_iter().find(|resource| *resource != giving_resource).unwrap();
+    let offer = Action::OfferTrade { giving: HashMap::from([(giving_resource, 1)]), receiving: HashMap::from([(receiving_resource, 1)]) };
+    game.takeAction(offer, 0).ok().unwrap();
+
+    // Every other seat is told, and sees the offer waiting for its response.
+    let mut other_connections = games.otherConnections(game_id, owner);
+    other_connections.sort();
+    assert_eq!(other_connections, vec![(other, Some(1)), (third, Some(2))]);
+    let offer_id = game.openTradeOfferIds()[0];
+    for (_, seat) in other_connections {
+        let view = game.view(seat);
+        assert_eq!(view.trade_offers.len(), 1);
+        assert_eq!(view.trade_offers[0].id, offer_id);
+        assert!(view.players[0].resource_cards.is_none());
+        assert!(game.legal_actions(seat.unwrap()).contains(&Action::DeclineTrade { offer_id }));
+    }
+}